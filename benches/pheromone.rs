@@ -0,0 +1,182 @@
+// Compares the dense pheromone grid with the HashMap storage it replaced,
+// using the access pattern of the ant behavior code: every ant samples the
+// grid around itself each tick, drops one pheromone, and then everything
+// evaporates.
+//
+// Run with `cargo bench --no-default-features --bench pheromone`.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use ant_simulacrum::pheromone::{PheromoneSystem, PheromoneType};
+use ant_simulacrum::rng::SimRng;
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 800;
+const CELL_SIZE: f32 = 10.0;
+const TICKS: usize = 60;
+const DELTA_TIME: f32 = 1.0 / 60.0;
+
+// 12 directions x 5 distances, like `Ant::find_strongest_pheromone_direction`
+const SAMPLE_DIRECTIONS: usize = 12;
+const SAMPLE_DISTANCES: [f32; 5] = [5.0, 10.0, 20.0, 30.0, 40.0];
+
+// The storage the pheromone system used before the dense grid
+struct HashMapPheromones {
+    pheromones: HashMap<(usize, usize, PheromoneType), f32>,
+    width: usize,
+    height: usize,
+}
+
+impl HashMapPheromones {
+    fn new() -> Self {
+        Self {
+            pheromones: HashMap::new(),
+            width: (WIDTH as f32 / CELL_SIZE) as usize,
+            height: (HEIGHT as f32 / CELL_SIZE) as usize,
+        }
+    }
+
+    fn add_pheromone(&mut self, x: f32, y: f32, pheromone_type: PheromoneType, strength: f32) {
+        let grid_x = (x / CELL_SIZE) as usize;
+        let grid_y = (y / CELL_SIZE) as usize;
+        if grid_x >= self.width || grid_y >= self.height {
+            return;
+        }
+
+        let key = (grid_x, grid_y, pheromone_type);
+        let current_strength = self.pheromones.get(&key).unwrap_or(&0.0);
+        self.pheromones.insert(key, (current_strength + strength).min(1.0));
+    }
+
+    fn get_pheromone(&self, x: f32, y: f32, pheromone_type: &PheromoneType) -> f32 {
+        let grid_x = (x / CELL_SIZE) as usize;
+        let grid_y = (y / CELL_SIZE) as usize;
+        if grid_x >= self.width || grid_y >= self.height {
+            return 0.0;
+        }
+
+        *self.pheromones.get(&(grid_x, grid_y, *pheromone_type)).unwrap_or(&0.0)
+    }
+
+    fn update(&mut self, delta_time: f32) {
+        let evaporation_rate = 0.005 * delta_time;
+        let mut to_remove = Vec::new();
+
+        for (key, strength) in self.pheromones.iter_mut() {
+            *strength -= evaporation_rate;
+            if *strength <= 0.001 {
+                to_remove.push(*key);
+            }
+        }
+
+        for key in to_remove {
+            self.pheromones.remove(&key);
+        }
+    }
+}
+
+// The operations the benchmark needs from either storage
+trait Pheromones {
+    fn add(&mut self, x: f32, y: f32, pheromone_type: PheromoneType, strength: f32);
+    fn get(&self, x: f32, y: f32, pheromone_type: PheromoneType) -> f32;
+    fn evaporate(&mut self, delta_time: f32);
+}
+
+impl Pheromones for PheromoneSystem {
+    fn add(&mut self, x: f32, y: f32, pheromone_type: PheromoneType, strength: f32) {
+        self.add_pheromone(x, y, pheromone_type, None, strength);
+    }
+
+    fn get(&self, x: f32, y: f32, pheromone_type: PheromoneType) -> f32 {
+        self.get_pheromone(x, y, &pheromone_type, None)
+    }
+
+    fn evaporate(&mut self, delta_time: f32) {
+        self.update(delta_time);
+    }
+}
+
+impl Pheromones for HashMapPheromones {
+    fn add(&mut self, x: f32, y: f32, pheromone_type: PheromoneType, strength: f32) {
+        self.add_pheromone(x, y, pheromone_type, strength);
+    }
+
+    fn get(&self, x: f32, y: f32, pheromone_type: PheromoneType) -> f32 {
+        self.get_pheromone(x, y, &pheromone_type)
+    }
+
+    fn evaporate(&mut self, delta_time: f32) {
+        self.update(delta_time);
+    }
+}
+
+struct BenchAnt {
+    x: f32,
+    y: f32,
+    direction: f32,
+}
+
+// Run `TICKS` ticks of `ant_count` wandering ants against the storage
+fn run<P: Pheromones>(pheromones: &mut P, ant_count: usize) -> Duration {
+    let mut rng = SimRng::new(42);
+    let mut ants: Vec<BenchAnt> = (0..ant_count)
+        .map(|_| BenchAnt {
+            x: rng.gen::<f32>() * WIDTH as f32,
+            y: rng.gen::<f32>() * HEIGHT as f32,
+            direction: rng.gen::<f32>() * std::f32::consts::TAU,
+        })
+        .collect();
+
+    let start = Instant::now();
+    for _ in 0..TICKS {
+        for (i, ant) in ants.iter_mut().enumerate() {
+            let pheromone_type = if i % 2 == 0 { PheromoneType::Food } else { PheromoneType::Home };
+
+            let mut best = 0.0f32;
+            for direction in 0..SAMPLE_DIRECTIONS {
+                let angle = ant.direction + direction as f32 / SAMPLE_DIRECTIONS as f32 * std::f32::consts::TAU;
+                for distance in SAMPLE_DISTANCES {
+                    let strength = pheromones.get(
+                        ant.x + angle.cos() * distance,
+                        ant.y + angle.sin() * distance,
+                        pheromone_type,
+                    );
+                    best = best.max(strength);
+                }
+            }
+            black_box(best);
+
+            pheromones.add(ant.x, ant.y, pheromone_type, 0.3);
+
+            ant.direction += (rng.gen::<f32>() - 0.5) * 0.5;
+            ant.x = (ant.x + ant.direction.cos() * 20.0 * DELTA_TIME).rem_euclid(WIDTH as f32);
+            ant.y = (ant.y + ant.direction.sin() * 20.0 * DELTA_TIME).rem_euclid(HEIGHT as f32);
+        }
+
+        pheromones.evaporate(DELTA_TIME);
+    }
+    start.elapsed()
+}
+
+fn main() {
+    println!("{} ticks on a {}x{} world ({} samples per ant per tick)",
+        TICKS, WIDTH, HEIGHT, SAMPLE_DIRECTIONS * SAMPLE_DISTANCES.len());
+    println!("{:>8} {:>14} {:>14} {:>8}", "ants", "hashmap", "dense grid", "speedup");
+
+    for ant_count in [1_000, 10_000] {
+        let hashmap = run(&mut HashMapPheromones::new(), ant_count);
+        let dense = run(&mut PheromoneSystem::new(WIDTH, HEIGHT, CELL_SIZE), ant_count);
+
+        println!(
+            "{:>8} {:>11.1} ms {:>11.1} ms {:>7.1}x",
+            ant_count,
+            hashmap.as_secs_f64() * 1000.0,
+            dense.as_secs_f64() * 1000.0,
+            hashmap.as_secs_f64() / dense.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use serde::{Serialize, Deserialize};
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+// Tuning for how ants behave. Every colony has its own copy, so colonies can
+// be tuned differently; ants without a colony use the defaults. Fields left
+// out of a JSON file keep their default values. Angles are in radians,
+// times in seconds, distances in pixels and chances are between 0 and 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AntParams {
+    // Bodies
+    pub speed: f32,                        // Normal walking speed in pixels per second
+    pub max_health: f32,                   // Health of a well-fed ant
+    pub hunger_rate: f32,                  // Health lost per second away from the nest; 0 turns starvation off
+    pub soldier_share: f32,                // Share of new ants that become soldiers
+
+    // Pheromone deposits
+    pub deposit_interval: f32,             // Time between trail deposits
+    pub home_trail_strength: f32,          // Home trail laid right as an ant leaves the nest
+    pub food_trail_strength: f32,          // Food trail laid right as an ant leaves a food source
+    pub trail_fade_time: f32,              // Time for an ant's deposits to fade to about a third
+    pub food_site_strength: f32,           // Food pheromone left where food is picked up
+    pub nest_strength: f32,                // Home pheromone left where food is delivered
+    pub circle_escape_strength: f32,       // Home pheromone left after escaping a circle
+    pub alarm_strength: f32,               // Alarm pheromone left by an ant in trouble
+    pub wounded_health: f32,               // Share of health below which an ant raises the alarm
+
+    // Heading home with food
+    pub homing_after_food: f32,            // Share of the turn toward home while ignoring trails
+    pub strong_homing_chance: f32,         // Chance per tick of a strong turn toward home
+    pub strong_homing: f32,                // Share of the turn toward home on a strong turn
+    pub weak_homing: f32,                  // Share of the turn toward home otherwise
+    pub homing_jitter: f32,                // Random wobble added while heading home
+
+    // Wandering
+    pub random_turn_chance: f32,           // Chance per second of a random turn
+    pub random_turn_angle: f32,            // Widest random turn
+    pub follow_chance: f32,                // Chance per tick of following trails at all
+    pub lost_turn_chance_carrying: f32,    // Chance per tick of a turn when no trail is found, carrying food
+    pub lost_turn_chance_searching: f32,   // The same for searching ants
+    pub lost_turn_angle_carrying: f32,     // Widest turn when no trail is found, carrying food
+    pub lost_turn_angle_searching: f32,    // The same for searching ants
+
+    // Direction sampler steering
+    pub sense_distance: f32,               // Furthest point sampled
+    pub min_sense_distance: f32,           // Nearest point sampled
+    pub home_trail_threshold: f32,         // Weakest home trail followed by ants carrying food
+    pub trail_threshold: f32,              // Weakest trail followed otherwise
+    pub carrying_directions: usize,        // Directions sampled by ants carrying food
+    pub searching_directions: usize,       // Directions sampled by searching ants
+    pub carrying_sample_arc: f32,          // Width of the forward arc sampled by ants carrying food
+    pub carrying_sample_fractions: [f32; 3], // Points sampled between the nearest and furthest, as shares of the furthest, carrying food
+    pub searching_sample_fractions: [f32; 3], // The same for searching ants
+    pub carrying_back_angle: f32,          // Ants carrying food skip directions further than this from their heading
+    pub forward_bias: f32,                 // Extra strength given to straight ahead for ants carrying food
+    pub carrying_forward_limit: f32,       // Widest turn toward a trail for ants carrying food
+    pub searching_forward_limit: f32,      // Widest turn toward a trail for searching ants
+    pub carrying_turn_rate: f32,           // Share of the turn toward a trail per tick, carrying food
+    pub searching_turn_rate: f32,          // The same for searching ants
+    pub big_turn_chance: f32,              // Chance of turning toward a trail behind the ant anyway
+    pub big_turn_rate: f32,                // Share of the turn made then
+    pub carrying_jitter: f32,              // Random wobble while following a trail, carrying food
+    pub searching_jitter: f32,             // The same for searching ants
+
+    // Three-sensor steering
+    pub sensor_distance: f32,              // How far ahead the antennae reach
+    pub sensor_angle: f32,                 // Angle between straight ahead and each antenna
+    pub sensor_turn_speed: f32,            // Turn toward the stronger antenna, in radians per second
+    pub sensor_threshold: f32,             // Weakest pheromone noticed
+
+    // Gradient-ascent steering
+    pub gradient_lookahead: f32,           // How far ahead the slope is felt
+    pub gradient_forward_limit: f32,       // Widest angle from the heading that is climbed
+    pub gradient_turn_rate: f32,           // Share of the turn uphill per tick
+    pub gradient_threshold: f32,           // Flattest slope followed, in strength per pixel
+
+    // Random wobble per tick for the three-sensor and gradient-ascent steering
+    pub steering_jitter: f32,
+
+    // Alarms
+    pub alarm_directions: usize,           // Directions sampled for alarm pheromone
+    pub alarm_turn_rate: f32,              // Share of the turn toward or away from an alarm per tick
+
+    // Picking up and delivering food
+    pub ignore_after_food: f32,            // Time trails are ignored after picking up food
+    pub food_escape_distance: f32,         // Step away from a food source after picking up food
+    pub pickup_jitter: f32,                // Random wobble of the heading home after picking up food
+    pub departure_jitter: f32,             // Random wobble of the heading out after delivering food
+
+    // Circle detection
+    pub position_record_interval: f32,     // Time between recorded positions
+    pub position_history_size: usize,      // Recorded positions kept
+    pub circle_detection_threshold: f32,   // Distance to an older position that counts as circling
+    pub circle_min_age: f32,               // Only positions at least this old are compared
+    pub circle_escape_time: f32,           // Circling for longer than this makes the ant break out
+    pub ignore_after_circle_carrying: f32, // Time trails are ignored after breaking out, carrying food
+    pub ignore_after_circle_searching: f32, // The same for searching ants
+    pub escape_speed: f32,                 // Walking speed after breaking out, carrying food
+    pub speed_restore_time: f32,           // Normal speed returns when this much ignore time is left
+    pub circle_escape_step: f32,           // Step toward home when breaking out, carrying food
+    pub circle_escape_jitter: f32,         // Random wobble of that heading home
+    pub circle_escape_turn: f32,           // Widest random turn when breaking out, searching
+
+    // Bouncing off walls and the edges of the map
+    pub wall_bounce_jitter: f32,           // Random wobble after bouncing off a wall
+    pub edge_margin: f32,                  // How close ants get to the edges of the map
+    pub edge_bounce_turn: f32,             // Widest random turn after bouncing off an edge
+    pub edge_bounce_step: f32,             // Step away from an edge after bouncing off it
+    pub ignore_after_bounce: f32,          // Time trails are ignored after bouncing off an edge
+}
+
+impl AntParams {
+    pub const DEFAULT: AntParams = AntParams {
+        speed: 20.0,
+        max_health: 100.0,
+        hunger_rate: 0.0, // Ants don't starve unless asked to; 0.5 makes a full ant last 200 seconds
+        soldier_share: 0.2,
+
+        deposit_interval: 0.5,
+        home_trail_strength: 0.3,
+        food_trail_strength: 0.8,
+        trail_fade_time: 30.0,
+        food_site_strength: 0.9,
+        nest_strength: 0.9,
+        circle_escape_strength: 0.9,
+        alarm_strength: 1.0,
+        wounded_health: 0.25,
+
+        homing_after_food: 0.85,
+        strong_homing_chance: 0.45,
+        strong_homing: 0.7,
+        weak_homing: 0.3,
+        homing_jitter: 0.15,
+
+        random_turn_chance: 0.05,
+        random_turn_angle: PI,
+        follow_chance: 0.9,
+        lost_turn_chance_carrying: 0.8,
+        lost_turn_chance_searching: 0.4,
+        lost_turn_angle_carrying: PI * 0.8,
+        lost_turn_angle_searching: PI * 0.5,
+
+        sense_distance: 40.0,
+        min_sense_distance: 5.0,
+        home_trail_threshold: 0.002,
+        trail_threshold: 0.05,
+        carrying_directions: 6,
+        searching_directions: 12,
+        carrying_sample_arc: PI * 0.67,
+        carrying_sample_fractions: [0.3, 0.5, 0.8],
+        searching_sample_fractions: [0.25, 0.5, 0.75],
+        carrying_back_angle: PI * 0.4,
+        forward_bias: 0.1,
+        carrying_forward_limit: PI * 0.5,
+        searching_forward_limit: PI * 2.0 / 3.0,
+        carrying_turn_rate: 0.1,
+        searching_turn_rate: 0.7,
+        big_turn_chance: 0.1,
+        big_turn_rate: 0.4,
+        carrying_jitter: 0.02,
+        searching_jitter: 0.2,
+
+        sensor_distance: 15.0,
+        sensor_angle: PI / 4.0,
+        sensor_turn_speed: PI,
+        sensor_threshold: 0.01,
+
+        gradient_lookahead: 10.0,
+        gradient_forward_limit: PI * 2.0 / 3.0,
+        gradient_turn_rate: 0.3,
+        gradient_threshold: 0.0002,
+
+        steering_jitter: 0.1,
+
+        alarm_directions: 8,
+        alarm_turn_rate: 0.5,
+
+        ignore_after_food: 15.0,
+        food_escape_distance: 15.0,
+        pickup_jitter: 0.5,
+        departure_jitter: 0.5,
+
+        position_record_interval: 0.3,
+        position_history_size: 30,
+        circle_detection_threshold: 10.0,
+        circle_min_age: 1.5,
+        circle_escape_time: 1.5,
+        ignore_after_circle_carrying: 25.0,
+        ignore_after_circle_searching: 10.0,
+        escape_speed: 5.0,
+        speed_restore_time: 1.0,
+        circle_escape_step: 8.0,
+        circle_escape_jitter: 1.0,
+        circle_escape_turn: PI * 1.5,
+
+        wall_bounce_jitter: 0.2,
+        edge_margin: 10.0,
+        edge_bounce_turn: PI / 2.0,
+        edge_bounce_step: 3.0,
+        ignore_after_bounce: 1.0,
+    };
+
+    // Check that the tuning makes sense: times, distances, speeds and sample
+    // counts the ants divide by or step with must be positive, everything else
+    // that switches off at 0 must not be negative, and chances and shares
+    // must lie between 0 and 1. Returns what is wrong with the first bad field.
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [
+            ("speed", self.speed),
+            ("max_health", self.max_health),
+            ("deposit_interval", self.deposit_interval),
+            ("trail_fade_time", self.trail_fade_time),
+            ("sense_distance", self.sense_distance),
+            ("min_sense_distance", self.min_sense_distance),
+            ("carrying_directions", self.carrying_directions as f32),
+            ("searching_directions", self.searching_directions as f32),
+            ("sensor_distance", self.sensor_distance),
+            ("sensor_turn_speed", self.sensor_turn_speed),
+            ("gradient_lookahead", self.gradient_lookahead),
+            ("alarm_directions", self.alarm_directions as f32),
+            ("position_record_interval", self.position_record_interval),
+            ("circle_detection_threshold", self.circle_detection_threshold),
+            ("circle_escape_time", self.circle_escape_time),
+            ("escape_speed", self.escape_speed),
+        ];
+        for (name, value) in positive {
+            if value.is_nan() || value <= 0.0 {
+                return Err(format!("{} must be positive, got {}", name, value));
+            }
+        }
+
+        let not_negative = [
+            ("hunger_rate", self.hunger_rate),
+            ("home_trail_strength", self.home_trail_strength),
+            ("food_trail_strength", self.food_trail_strength),
+            ("food_site_strength", self.food_site_strength),
+            ("nest_strength", self.nest_strength),
+            ("circle_escape_strength", self.circle_escape_strength),
+            ("alarm_strength", self.alarm_strength),
+            ("homing_jitter", self.homing_jitter),
+            ("random_turn_chance", self.random_turn_chance),
+            ("random_turn_angle", self.random_turn_angle),
+            ("lost_turn_angle_carrying", self.lost_turn_angle_carrying),
+            ("lost_turn_angle_searching", self.lost_turn_angle_searching),
+            ("home_trail_threshold", self.home_trail_threshold),
+            ("trail_threshold", self.trail_threshold),
+            ("carrying_sample_arc", self.carrying_sample_arc),
+            ("carrying_back_angle", self.carrying_back_angle),
+            ("forward_bias", self.forward_bias),
+            ("carrying_forward_limit", self.carrying_forward_limit),
+            ("searching_forward_limit", self.searching_forward_limit),
+            ("carrying_jitter", self.carrying_jitter),
+            ("searching_jitter", self.searching_jitter),
+            ("sensor_angle", self.sensor_angle),
+            ("sensor_threshold", self.sensor_threshold),
+            ("gradient_forward_limit", self.gradient_forward_limit),
+            ("gradient_threshold", self.gradient_threshold),
+            ("steering_jitter", self.steering_jitter),
+            ("ignore_after_food", self.ignore_after_food),
+            ("food_escape_distance", self.food_escape_distance),
+            ("pickup_jitter", self.pickup_jitter),
+            ("departure_jitter", self.departure_jitter),
+            ("circle_min_age", self.circle_min_age),
+            ("ignore_after_circle_carrying", self.ignore_after_circle_carrying),
+            ("ignore_after_circle_searching", self.ignore_after_circle_searching),
+            ("speed_restore_time", self.speed_restore_time),
+            ("circle_escape_step", self.circle_escape_step),
+            ("circle_escape_jitter", self.circle_escape_jitter),
+            ("circle_escape_turn", self.circle_escape_turn),
+            ("wall_bounce_jitter", self.wall_bounce_jitter),
+            ("edge_margin", self.edge_margin),
+            ("edge_bounce_turn", self.edge_bounce_turn),
+            ("edge_bounce_step", self.edge_bounce_step),
+            ("ignore_after_bounce", self.ignore_after_bounce),
+        ];
+        for (name, value) in not_negative {
+            if value.is_nan() || value < 0.0 {
+                return Err(format!("{} must not be negative, got {}", name, value));
+            }
+        }
+
+        let shares = [
+            ("soldier_share", self.soldier_share),
+            ("wounded_health", self.wounded_health),
+            ("homing_after_food", self.homing_after_food),
+            ("strong_homing_chance", self.strong_homing_chance),
+            ("strong_homing", self.strong_homing),
+            ("weak_homing", self.weak_homing),
+            ("follow_chance", self.follow_chance),
+            ("lost_turn_chance_carrying", self.lost_turn_chance_carrying),
+            ("lost_turn_chance_searching", self.lost_turn_chance_searching),
+            ("carrying_turn_rate", self.carrying_turn_rate),
+            ("searching_turn_rate", self.searching_turn_rate),
+            ("big_turn_chance", self.big_turn_chance),
+            ("big_turn_rate", self.big_turn_rate),
+            ("gradient_turn_rate", self.gradient_turn_rate),
+            ("alarm_turn_rate", self.alarm_turn_rate),
+            ("carrying_sample_fractions", self.carrying_sample_fractions[0]),
+            ("carrying_sample_fractions", self.carrying_sample_fractions[1]),
+            ("carrying_sample_fractions", self.carrying_sample_fractions[2]),
+            ("searching_sample_fractions", self.searching_sample_fractions[0]),
+            ("searching_sample_fractions", self.searching_sample_fractions[1]),
+            ("searching_sample_fractions", self.searching_sample_fractions[2]),
+        ];
+        for (name, value) in shares {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 1, got {}", name, value));
+            }
+        }
+
+        if self.min_sense_distance > self.sense_distance {
+            return Err(format!(
+                "min_sense_distance ({}) must not be more than sense_distance ({})",
+                self.min_sense_distance, self.sense_distance
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for AntParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+// Read ant tuning from a JSON file, e.g. `{ "speed": 25.0, "sense_distance": 60.0 }`
+pub fn load_ant_params(path: &Path) -> io::Result<AntParams> {
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    let params: AntParams = serde_json::from_str(&contents)?;
+    params.validate().map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))?;
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_params_are_valid() {
+        assert_eq!(AntParams::DEFAULT.validate(), Ok(()));
+    }
+
+    #[test]
+    fn zero_fade_time_is_rejected() {
+        let params = AntParams { trail_fade_time: 0.0, ..AntParams::DEFAULT };
+        assert!(params.validate().unwrap_err().contains("trail_fade_time"));
+    }
+
+    #[test]
+    fn negative_or_nan_values_are_rejected() {
+        let params = AntParams { hunger_rate: -1.0, ..AntParams::DEFAULT };
+        assert!(params.validate().unwrap_err().contains("hunger_rate"));
+        let params = AntParams { speed: f32::NAN, ..AntParams::DEFAULT };
+        assert!(params.validate().unwrap_err().contains("speed"));
+        let params = AntParams { follow_chance: 1.5, ..AntParams::DEFAULT };
+        assert!(params.validate().unwrap_err().contains("follow_chance"));
+        let params = AntParams { searching_directions: 0, ..AntParams::DEFAULT };
+        assert!(params.validate().unwrap_err().contains("searching_directions"));
+    }
+}
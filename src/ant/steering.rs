@@ -0,0 +1,194 @@
+use log::debug;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::environment::Environment;
+use crate::math::Vector2f;
+use crate::pheromone::PheromoneType;
+use super::{rad_to_deg, Ant};
+
+// Which steering strategy a colony's ants use to follow trails.
+// Colonies can use different strategies, so they can be compared in the same world.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Steering {
+    // Sample many directions and distances and head for the strongest
+    #[default]
+    Sampler,
+    // Compare a left, forward and right antenna and turn toward the strongest
+    ThreeSensor,
+    // Climb the smoothed pheromone gradient under the ant
+    GradientAscent,
+}
+
+impl Steering {
+    // Every steering strategy, in declaration order
+    pub const ALL: [Steering; 3] = [Steering::Sampler, Steering::ThreeSensor, Steering::GradientAscent];
+
+    pub fn strategy(self) -> &'static dyn SteeringStrategy {
+        match self {
+            Steering::Sampler => &DirectionSampler,
+            Steering::ThreeSensor => &ThreeSensor,
+            Steering::GradientAscent => &GradientAscent,
+        }
+    }
+}
+
+impl std::str::FromStr for Steering {
+    type Err = String;
+
+    // Parse the names used in scenario files, e.g. `three_sensor`
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "sampler" => Ok(Steering::Sampler),
+            "three_sensor" => Ok(Steering::ThreeSensor),
+            "gradient_ascent" => Ok(Steering::GradientAscent),
+            _ => Err(format!("Unknown steering strategy: {}", name)),
+        }
+    }
+}
+
+// How an ant turns to follow a pheromone trail
+pub trait SteeringStrategy {
+    // Turn the ant along the trail of `pheromone_type`.
+    // Returns whether there was a trail to follow; if not the ant wanders instead.
+    fn steer(&self, ant: &mut Ant, pheromone_type: PheromoneType, delta_time: f32, environment: &mut Environment) -> bool;
+}
+
+// The original steering: look for the strongest pheromone over many
+// directions and distances and turn toward it. Ants carrying food look in a
+// narrower forward arc and turn more gently, so they don't backtrack.
+pub struct DirectionSampler;
+
+impl SteeringStrategy for DirectionSampler {
+    fn steer(&self, ant: &mut Ant, pheromone_type: PheromoneType, _delta_time: f32, environment: &mut Environment) -> bool {
+        // Check pheromones in multiple directions
+        let num_directions = if ant.state.carrying_food {
+            ant.params.carrying_directions  // Fewer directions make carrying ants less twitchy
+        } else {
+            ant.params.searching_directions
+        };
+
+        let Some(best_dir) = ant.find_strongest_pheromone_direction(environment, pheromone_type, num_directions) else {
+            return false;
+        };
+
+        // Calculate the angle difference between current direction and pheromone direction
+        let angle_diff = (best_dir - ant.velocity.direction + std::f32::consts::PI * 3.0) % (std::f32::consts::PI * 2.0) - std::f32::consts::PI;
+
+        // Improved logic to prevent circular trails
+        // Only turn if the pheromone is roughly ahead of us (wider angle when carrying food)
+        let forward_angle_limit = if ant.state.carrying_food {
+            ant.params.carrying_forward_limit
+        } else {
+            ant.params.searching_forward_limit
+        };
+
+        if angle_diff.abs() < forward_angle_limit {
+            // Gradually turn towards the best direction
+            // Reduced turn rate for carrying ants to make movement more stable
+            let turn_rate = if ant.state.carrying_food {
+                ant.params.carrying_turn_rate
+            } else {
+                ant.params.searching_turn_rate
+            };
+
+            let old_direction = ant.velocity.direction;
+            ant.velocity.direction += angle_diff * turn_rate;
+
+            if ant.state.carrying_food {
+                debug!(
+                    "Ant #{} following pheromone - old_dir={:.0}° new_dir={:.0}° diff={:.0}° turn_rate={:.1}",
+                    ant.id,
+                    rad_to_deg(old_direction),
+                    rad_to_deg(ant.velocity.direction),
+                    rad_to_deg(angle_diff),
+                    turn_rate
+                );
+            }
+        } else if environment.rng().gen::<f32>() < ant.params.big_turn_chance {
+            // Small chance to make a big turn anyway, to avoid getting stuck
+            ant.velocity.direction += angle_diff * ant.params.big_turn_rate;
+
+            if ant.state.carrying_food {
+                debug!(
+                    "Ant #{} made BIG TURN to avoid getting stuck, new_dir={:.0}°",
+                    ant.id,
+                    rad_to_deg(ant.velocity.direction)
+                );
+            }
+        }
+
+        // Add a small random variation to prevent perfect following that might lead to circles
+        // Use smaller variation for carrying ants to prevent erratic movement
+        let random_variation = if ant.state.carrying_food {
+            (environment.rng().gen::<f32>() - 0.5) * ant.params.carrying_jitter
+        } else {
+            (environment.rng().gen::<f32>() - 0.5) * ant.params.searching_jitter
+        };
+        ant.velocity.direction += random_variation;
+
+        true
+    }
+}
+
+// The classic antenna model: smell a little way ahead, straight on and to
+// either side, and turn toward whichever side smells strongest.
+pub struct ThreeSensor;
+
+impl SteeringStrategy for ThreeSensor {
+    fn steer(&self, ant: &mut Ant, pheromone_type: PheromoneType, delta_time: f32, environment: &mut Environment) -> bool {
+        let reading = environment.pheromone_system_ref().sense_antennae(
+            Vector2f::new(ant.position.x, ant.position.y),
+            ant.velocity.direction,
+            ant.params.sensor_angle,
+            ant.params.sensor_distance,
+            pheromone_type,
+            ant.state.colony_id,
+        );
+
+        if reading.left.max(reading.centre).max(reading.right) < ant.params.sensor_threshold {
+            return false;
+        }
+
+        // Keep going while the trail is strongest ahead, otherwise turn toward the stronger antenna
+        let turn = ant.params.sensor_turn_speed * delta_time;
+        if reading.centre < reading.left || reading.centre < reading.right {
+            ant.velocity.direction += if reading.left > reading.right { -turn } else { turn };
+        }
+
+        ant.velocity.direction += (environment.rng().gen::<f32>() - 0.5) * ant.params.steering_jitter;
+        true
+    }
+}
+
+// Turn up the slope of the pheromone field under the ant, using the
+// interpolated gradient rather than sampling discrete directions.
+pub struct GradientAscent;
+
+impl SteeringStrategy for GradientAscent {
+    fn steer(&self, ant: &mut Ant, pheromone_type: PheromoneType, _delta_time: f32, environment: &mut Environment) -> bool {
+        // Feel the slope a little way ahead, where the ant is about to walk
+        let gradient = environment.pheromone_system_ref().gradient(
+            ant.position.x + ant.velocity.direction.cos() * ant.params.gradient_lookahead,
+            ant.position.y + ant.velocity.direction.sin() * ant.params.gradient_lookahead,
+            pheromone_type,
+            ant.state.colony_id,
+        );
+
+        if gradient.x.hypot(gradient.y) < ant.params.gradient_threshold {
+            return false;
+        }
+
+        // Only climb slopes that lie ahead, so ants don't turn back onto the trail behind them
+        let uphill = gradient.y.atan2(gradient.x);
+        let angle_diff = (uphill - ant.velocity.direction + std::f32::consts::PI * 3.0) % (std::f32::consts::PI * 2.0) - std::f32::consts::PI;
+        if angle_diff.abs() > ant.params.gradient_forward_limit {
+            return false;
+        }
+        ant.velocity.direction += angle_diff * ant.params.gradient_turn_rate;
+
+        ant.velocity.direction += (environment.rng().gen::<f32>() - 0.5) * ant.params.steering_jitter;
+        true
+    }
+}
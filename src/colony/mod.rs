@@ -1,51 +1,53 @@
-use sfml::graphics::{RenderWindow, Text, Color, Font, Transformable, Shape, RenderTarget};
-use sfml::system::Vector2f;
 use log::debug;
 
-use crate::ant::Ant;
-use crate::environment::Environment;
+use crate::ant::{AntParams, Steering};
+use crate::ecs::component::impl_component;
+use crate::math::Vector2f;
 
-#[derive(Default)]
+// Number of ants a new colony starts with
+const INITIAL_ANTS: usize = 10;
+
+// Food a colony spends to raise one new ant
+const FOOD_PER_ANT: f32 = 10.0;
+
+// A colony lives on its own entity in the ECS world.
+// The colony's ants are separate entities that point back at it through
+// `AntStateComponent::colony_id`; the ColonySystem counts them and spawns new ones.
+#[derive(Clone, Debug, Default)]
 pub struct Colony {
     position: Vector2f,
     radius: f32,
-    ants: Vec<Ant>,
+    ant_count: usize,
+    pending_spawns: usize, // Ants waiting to be spawned by the ColonySystem
     food_stored: f32,
     max_ants: usize,
     food_deliveries: u32, // Track the number of food deliveries
+    steering: Steering, // How the colony's ants follow trails
+    params: AntParams, // Tuning for the colony's ants
 }
 
 impl Colony {
     pub fn new(position: Vector2f, radius: f32) -> Self {
         debug!("Creating new colony at position ({},{})", position.x, position.y);
-        let mut colony = Self {
+        Self {
             position,
             radius,
-            ants: Vec::new(),
+            ant_count: 0,
+            pending_spawns: INITIAL_ANTS,
             food_stored: 0.0,
             max_ants: 50,  // Start with a small cap
             food_deliveries: 0, // Start with no deliveries
-        };
-        
-        // Create initial ants
-        for _ in 0..10 {
-            colony.ants.push(Ant::new(position.x, position.y));
+            steering: Steering::default(),
+            params: AntParams::default(),
         }
-        
-        colony
     }
     
-    pub fn update(&mut self, delta_time: f32, environment: &mut Environment) {
-        // Update all ants
-        for ant in &mut self.ants {
-            ant.update(delta_time, environment);
-        }
-        
+    pub fn update(&mut self) {
         // Colony management (spawn new ants, etc.)
         // For now, just maintain a certain number of ants
-        if self.ants.len() < self.max_ants && self.food_stored > 10.0 {
-            self.ants.push(Ant::new(self.position.x, self.position.y));
-            self.food_stored -= 10.0;
+        if self.ant_count + self.pending_spawns < self.max_ants && self.food_stored > FOOD_PER_ANT {
+            self.pending_spawns += 1;
+            self.food_stored -= FOOD_PER_ANT;
         }
     }
     
@@ -53,80 +55,43 @@ impl Colony {
         self.food_stored += amount;
         self.food_deliveries += 1; // Count each food delivery
         
-        println!("DEBUG: Colony at ({:.1},{:.1}) food delivery! Amount: {}, Total stored: {:.1}, Deliveries count: {}", 
-            self.position.x, self.position.y, amount, self.food_stored, self.food_deliveries);
-        
         // Food allows colony to grow
         if self.food_stored > 100.0 && self.max_ants < 200 {
             self.max_ants += 1;
         }
     }
     
-    pub fn render(&self, window: &mut RenderWindow) {
-        // Render all ants
-        for ant in &self.ants {
-            ant.render(window);
-        }
-        
-        // Render food information above the colony
-        self.render_food_info(window);
+    // Hand over the ants that are ready to be spawned
+    pub fn take_pending_spawns(&mut self) -> usize {
+        std::mem::take(&mut self.pending_spawns)
     }
     
-    fn render_food_info(&self, window: &mut RenderWindow) {
-        // Create a text representation of food information
-        // This depends on having a static font reference, which we can't easily get here
-        // We'll use a colored circle with size proportional to food instead
-        
-        // Draw a circular indicator for stored food - gold/yellow
-        let radius = (self.food_stored / 10.0).min(8.0).max(2.0); // Size relative to food amount
-        let mut food_indicator = sfml::graphics::CircleShape::new(radius, 6);
-        food_indicator.set_fill_color(Color::rgb(255, 215, 0)); // Gold
-        food_indicator.set_position(Vector2f::new(
-            self.position.x - radius, 
-            self.position.y - 30.0 - radius // Position above colony
-        ));
-        window.draw(&food_indicator);
-        
-        // Draw a numerical indicator for deliveries - white background with number
-        let delivery_radius = 8.0;
-        let mut delivery_bg = sfml::graphics::CircleShape::new(delivery_radius, 12);
-        delivery_bg.set_fill_color(Color::rgb(255, 255, 255)); // White
-        delivery_bg.set_outline_thickness(1.0);
-        delivery_bg.set_outline_color(Color::rgb(0, 0, 0)); // Black outline
-        delivery_bg.set_position(Vector2f::new(
-            self.position.x + 15.0 - delivery_radius,
-            self.position.y - 30.0 - delivery_radius
-        ));
-        window.draw(&delivery_bg);
-        
-        // We can't directly render text here due to font lifetime issues
-        // A proper solution would be to modify the game to maintain fonts
-        // and pass them to Colony::render, but for now we'll use shapes
-        
-        // Draw simple indicators instead
-        if self.food_deliveries > 0 {
-            let size = delivery_radius * 0.7;
-            let mut marker = sfml::graphics::RectangleShape::new();
-            marker.set_size(Vector2f::new(size, size));
-            marker.set_fill_color(Color::rgb(0, 0, 0)); // Black
-            marker.set_position(Vector2f::new(
-                self.position.x + 15.0 - size/2.0,
-                self.position.y - 30.0 - size/2.0
-            ));
-            window.draw(&marker);
-        }
+    pub fn get_pending_spawns(&self) -> usize {
+        self.pending_spawns
+    }
+    
+    pub fn set_pending_spawns(&mut self, pending_spawns: usize) {
+        self.pending_spawns = pending_spawns;
     }
     
     pub fn get_statistics(&self) -> (usize, f32, usize, u32) {
-        (self.ants.len(), self.food_stored, self.max_ants, self.food_deliveries)
+        (self.ant_count, self.food_stored, self.max_ants, self.food_deliveries)
     }
     
     pub fn get_position(&self) -> Vector2f {
         self.position
     }
     
-    pub fn get_ants(&self) -> &Vec<Ant> {
-        &self.ants
+    pub fn get_radius(&self) -> f32 {
+        self.radius
+    }
+    
+    pub fn get_ant_count(&self) -> usize {
+        self.ant_count
+    }
+    
+    pub fn set_ant_count(&mut self, ant_count: usize) {
+        self.ant_count = ant_count;
     }
     
     pub fn get_food_stored(&self) -> f32 {
@@ -153,11 +118,23 @@ impl Colony {
         self.food_deliveries = deliveries;
     }
     
-    pub fn clear_ants(&mut self) {
-        self.ants.clear();
+    pub fn get_steering(&self) -> Steering {
+        self.steering
     }
     
-    pub fn add_ant(&mut self, ant: Ant) {
-        self.ants.push(ant);
+    pub fn set_steering(&mut self, steering: Steering) {
+        self.steering = steering;
     }
-} 
\ No newline at end of file
+    
+    pub fn get_params(&self) -> &AntParams {
+        &self.params
+    }
+    
+    // Retune the colony's ants. Takes effect on their next tick; speed,
+    // health and roles only change for ants spawned from now on.
+    pub fn set_params(&mut self, params: AntParams) {
+        self.params = params;
+    }
+}
+
+impl_component!(Colony, Colony);
@@ -1,5 +1,10 @@
 use std::any::Any;
-use sfml::system::Vector2f;
+use std::collections::VecDeque;
+
+use serde::{Serialize, Deserialize};
+
+use crate::math::Vector2f;
+use crate::pheromone::PheromoneType;
 
 /// Enum defining all possible component types
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -15,8 +20,35 @@ pub enum ComponentType {
     Colony,
 }
 
-/// Trait that all components must implement
+impl ComponentType {
+    /// Every component type, in declaration order
+    pub const ALL: [ComponentType; 9] = [
+        ComponentType::Position,
+        ComponentType::Velocity,
+        ComponentType::Appearance,
+        ComponentType::Health,
+        ComponentType::Pheromone,
+        ComponentType::AntState,
+        ComponentType::Collider,
+        ComponentType::Food,
+        ComponentType::Colony,
+    ];
+    
+    /// Number of component types
+    pub const COUNT: usize = Self::ALL.len();
+    
+    /// Position of this type in per-type tables, e.g. the world's component storages
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Trait that all components must implement.
+/// Implement it with `impl_component!` so the type can't be paired with the wrong `ComponentType`.
 pub trait Component: Any + Send + Sync {
+    /// Get the component type of this Rust type
+    fn static_type() -> ComponentType where Self: Sized;
+    
     /// Get the type of this component
     fn component_type(&self) -> ComponentType;
     
@@ -30,6 +62,34 @@ pub trait Component: Any + Send + Sync {
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
+/// Implement `Component` for a type, tying it to its `ComponentType` variant
+macro_rules! impl_component {
+    ($component:ty, $component_type:ident) => {
+        impl $crate::ecs::component::Component for $component {
+            fn static_type() -> $crate::ecs::component::ComponentType {
+                $crate::ecs::component::ComponentType::$component_type
+            }
+            
+            fn component_type(&self) -> $crate::ecs::component::ComponentType {
+                $crate::ecs::component::ComponentType::$component_type
+            }
+            
+            fn clone_boxed(&self) -> Box<dyn $crate::ecs::component::Component> {
+                Box::new(self.clone())
+            }
+            
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            
+            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+        }
+    };
+}
+pub(crate) use impl_component;
+
 /// Position component
 #[derive(Clone, Debug)]
 pub struct PositionComponent {
@@ -37,23 +97,7 @@ pub struct PositionComponent {
     pub y: f32,
 }
 
-impl Component for PositionComponent {
-    fn component_type(&self) -> ComponentType {
-        ComponentType::Position
-    }
-    
-    fn clone_boxed(&self) -> Box<dyn Component> {
-        Box::new(self.clone())
-    }
-    
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
+impl_component!(PositionComponent, Position);
 
 /// Velocity component
 #[derive(Clone, Debug)]
@@ -64,23 +108,7 @@ pub struct VelocityComponent {
     pub direction: f32, // in radians
 }
 
-impl Component for VelocityComponent {
-    fn component_type(&self) -> ComponentType {
-        ComponentType::Velocity
-    }
-    
-    fn clone_boxed(&self) -> Box<dyn Component> {
-        Box::new(self.clone())
-    }
-    
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
+impl_component!(VelocityComponent, Velocity);
 
 /// Appearance component
 #[derive(Clone, Debug)]
@@ -98,36 +126,53 @@ pub enum ShapeType {
     Rectangle,
 }
 
-impl Component for AppearanceComponent {
-    fn component_type(&self) -> ComponentType {
-        ComponentType::Appearance
-    }
-    
-    fn clone_boxed(&self) -> Box<dyn Component> {
-        Box::new(self.clone())
-    }
-    
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
-    
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
-    }
-}
+impl_component!(AppearanceComponent, Appearance);
 
 /// Ant state component for ant behavior
 #[derive(Clone, Debug)]
 pub struct AntStateComponent {
     pub state: AntState,
     pub carrying_food: bool,
+    pub carried_food: f32, // How much food the ant picked up, handed to its colony on delivery
     pub pheromone_timer: f32,
-    pub random_direction_timer: f32,
-    pub home_position: (f32, f32),
+    pub ignore_pheromones_timer: f32, // Timer to ignore pheromones after finding food
+    pub home_position: Vector2f,
     pub colony_id: Option<super::entity::EntityId>,
+    pub lifetime: f32, // Total lifetime of the ant
+    pub last_position_record: f32, // Time since last position recording
+    pub position_history: VecDeque<PositionRecord>, // Recent positions, used to detect circles
+    pub trail_time: f32, // Time since the ant left its nest or a food source; trails fade with it
+    pub role: AntRole,
+}
+
+impl AntStateComponent {
+    /// Create the state for a fresh ant that calls `home_position` home
+    pub fn new(home_position: Vector2f, colony_id: Option<super::entity::EntityId>) -> Self {
+        Self {
+            state: AntState::SearchingForFood,
+            carrying_food: false,
+            carried_food: 0.0,
+            pheromone_timer: 0.0,
+            ignore_pheromones_timer: 0.0,
+            home_position,
+            colony_id,
+            lifetime: 0.0,
+            last_position_record: 0.0,
+            position_history: VecDeque::new(),
+            trail_time: 0.0,
+            role: AntRole::Worker,
+        }
+    }
+}
+
+/// Position and timestamp of an ant, kept to detect circular movement
+#[derive(Clone, Debug)]
+pub struct PositionRecord {
+    pub position: Vector2f,
+    pub time: f32,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum AntState {
     Idle,
     SearchingForFood,
@@ -135,20 +180,125 @@ pub enum AntState {
     FollowingPheromone,
 }
 
-impl Component for AntStateComponent {
-    fn component_type(&self) -> ComponentType {
-        ComponentType::AntState
+/// What an ant does for its colony. Roles decide how an ant reacts to alarm pheromone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AntRole {
+    /// Forages, and flees from alarms
+    #[default]
+    Worker,
+    /// Forages too, but heads for alarms to defend the colony
+    Soldier,
+}
+
+impl_component!(AntStateComponent, AntState);
+
+/// Health component, for entities that can die
+#[derive(Clone, Debug)]
+pub struct HealthComponent {
+    pub current: f32,
+    pub max: f32,
+    pub decay_rate: f32, // Health lost per second, e.g. from hunger
+}
+
+impl HealthComponent {
+    /// Create a component at full health
+    pub fn new(max: f32, decay_rate: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            decay_rate,
+        }
+    }
+    
+    /// Whether the entity has run out of health
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
     }
     
-    fn clone_boxed(&self) -> Box<dyn Component> {
-        Box::new(self.clone())
+    /// Bring the entity back to full health
+    pub fn restore(&mut self) {
+        self.current = self.max;
+    }
+}
+
+impl_component!(HealthComponent, Health);
+
+/// Pheromone component, for entities that keep marking their position with a pheromone
+#[derive(Clone, Debug)]
+pub struct PheromoneComponent {
+    pub pheromone_type: PheromoneType,
+    pub strength: f32,
+    pub interval: f32, // Seconds between deposits
+    pub timer: f32, // Time left until the next deposit
+}
+
+impl PheromoneComponent {
+    /// Create an emitter that deposits right away and then every `interval` seconds
+    pub fn new(pheromone_type: PheromoneType, strength: f32, interval: f32) -> Self {
+        Self {
+            pheromone_type,
+            strength,
+            interval,
+            timer: 0.0,
+        }
+    }
+}
+
+impl_component!(PheromoneComponent, Pheromone);
+
+/// Which kind of entity a collider belongs to.
+/// Colliders on different layers touch. On the same layer only ants touch,
+/// so they can notice ants from other colonies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CollisionLayer {
+    Ant,
+    Food,
+    Colony,
+}
+
+impl CollisionLayer {
+    /// Whether colliders on these two layers report contacts
+    pub fn touches(self, other: CollisionLayer) -> bool {
+        self != other || self == CollisionLayer::Ant
+    }
+}
+
+/// Circle collider component
+#[derive(Clone, Debug)]
+pub struct ColliderComponent {
+    pub radius: f32,
+    pub layer: CollisionLayer,
+}
+
+impl_component!(ColliderComponent, Collider);
+
+/// Food component for a food source with a limited amount of food
+#[derive(Clone, Debug)]
+pub struct FoodComponent {
+    pub remaining: f32,
+    pub initial: f32,
+}
+
+impl FoodComponent {
+    /// Create a full food source
+    pub fn new(amount: f32) -> Self {
+        Self {
+            remaining: amount,
+            initial: amount,
+        }
     }
     
-    fn as_any(&self) -> &dyn Any {
-        self
+    /// Take up to `amount` food and return how much was taken
+    pub fn take(&mut self, amount: f32) -> f32 {
+        let taken = amount.min(self.remaining).max(0.0);
+        self.remaining -= taken;
+        taken
     }
     
-    fn as_any_mut(&mut self) -> &mut dyn Any {
-        self
+    /// Whether the source has run out of food
+    pub fn is_empty(&self) -> bool {
+        self.remaining <= 0.0
     }
-} 
\ No newline at end of file
+}
+
+impl_component!(FoodComponent, Food);
@@ -1,13 +1,45 @@
-use std::collections::HashMap;
+use std::fmt;
 use super::component::ComponentType;
 
-/// Unique identifier for entities
-pub type EntityId = u64;
+/// Unique identifier for entities.
+///
+/// The index says which entity slot in the world the entity lives in. Slots are
+/// reused once an entity is removed, and the generation is bumped every time,
+/// so an id kept around after its entity was removed never refers to the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+impl EntityId {
+    /// Create an id from its slot index and generation
+    pub fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+    
+    /// Slot the entity occupies in the world
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+    
+    /// How many times the slot had been reused when this id was handed out
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
 
 /// Entity struct representing a game object
 pub struct Entity {
     id: EntityId,
-    pub components: HashMap<ComponentType, usize>,
+    // Slot of each component in its type's storage, indexed by `ComponentType::index`
+    components: [Option<usize>; ComponentType::COUNT],
 }
 
 impl Entity {
@@ -15,7 +47,7 @@ impl Entity {
     pub fn new(id: EntityId) -> Self {
         Self {
             id,
-            components: HashMap::new(),
+            components: [None; ComponentType::COUNT],
         }
     }
     
@@ -26,21 +58,29 @@ impl Entity {
     
     /// Add a component to this entity
     pub fn add_component(&mut self, component_type: ComponentType, component_index: usize) {
-        self.components.insert(component_type, component_index);
+        self.components[component_type.index()] = Some(component_index);
     }
     
     /// Remove a component from this entity
     pub fn remove_component(&mut self, component_type: &ComponentType) {
-        self.components.remove(component_type);
+        self.components[component_type.index()] = None;
     }
     
     /// Check if entity has a specific component
     pub fn has_component(&self, component_type: &ComponentType) -> bool {
-        self.components.contains_key(component_type)
+        self.components[component_type.index()].is_some()
     }
     
     /// Get the component index for a specific component type
     pub fn get_component_index(&self, component_type: &ComponentType) -> Option<usize> {
-        self.components.get(component_type).copied()
+        self.components[component_type.index()]
+    }
+    
+    /// Iterate over the entity's components as (type, storage slot) pairs
+    pub fn components(&self) -> impl Iterator<Item = (ComponentType, usize)> + '_ {
+        ComponentType::ALL.iter()
+            .filter_map(move |&component_type| {
+                self.get_component_index(&component_type).map(|index| (component_type, index))
+            })
     }
 } 
\ No newline at end of file
@@ -0,0 +1,226 @@
+use std::collections::vec_deque;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::warn;
+
+/// Most events a channel buffers for subscribers that fall behind.
+/// Older events are dropped once a slow subscriber lets the buffer grow past this.
+pub const MAX_BUFFERED_EVENTS: usize = 65_536;
+
+/// Source of channel ids, so a subscription can tell which channel it belongs to
+static NEXT_CHANNEL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A typed event channel, stored as a world resource.
+///
+/// Anyone with access to the channel can `send` events. Subscribers get a
+/// `Subscription` and read every event sent after they subscribed, at their
+/// own pace. Events are kept until all subscribers have read them; with no
+/// subscribers events are dropped right away.
+pub struct EventChannel<T> {
+    // Unique per channel; subscriptions carry it so they only read their own channel
+    id: u64,
+    events: VecDeque<T>,
+    // Sequence number of the first buffered event
+    head: u64,
+    // Sequence number of the next event each subscriber reads, None once unsubscribed
+    cursors: Vec<Option<u64>>,
+    // Whether events have been dropped for a slow subscriber, so we only warn once
+    overflowed: bool,
+}
+
+/// A subscriber's read position in an `EventChannel<T>`
+pub struct Subscription<T> {
+    channel: u64,
+    id: usize,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<T> EventChannel<T> {
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_CHANNEL_ID.fetch_add(1, Ordering::Relaxed),
+            events: VecDeque::new(),
+            head: 0,
+            cursors: Vec::new(),
+            overflowed: false,
+        }
+    }
+
+    /// Publish an event to all current subscribers
+    pub fn send(&mut self, event: T) {
+        self.trim();
+        if !self.cursors.iter().any(Option::is_some) {
+            return;
+        }
+
+        self.events.push_back(event);
+
+        if self.events.len() > MAX_BUFFERED_EVENTS {
+            self.events.pop_front();
+            self.head += 1;
+
+            // Subscribers that hadn't read the dropped event skip it
+            for cursor in self.cursors.iter_mut().flatten() {
+                *cursor = (*cursor).max(self.head);
+            }
+
+            if !self.overflowed {
+                warn!("An event subscriber fell behind by more than {} events, dropping the oldest", MAX_BUFFERED_EVENTS);
+                self.overflowed = true;
+            }
+        }
+    }
+
+    /// Start receiving events. Only events sent from now on are delivered.
+    pub fn subscribe(&mut self) -> Subscription<T> {
+        let next = self.head + self.events.len() as u64;
+        let id = match self.cursors.iter().position(Option::is_none) {
+            Some(id) => {
+                self.cursors[id] = Some(next);
+                id
+            }
+            None => {
+                self.cursors.push(Some(next));
+                self.cursors.len() - 1
+            }
+        };
+
+        Subscription { channel: self.id, id, _event: PhantomData }
+    }
+
+    /// Stop receiving events
+    pub fn unsubscribe(&mut self, subscription: Subscription<T>) {
+        if let Some(cursor) = self.cursor_mut(&subscription) {
+            *cursor = None;
+        }
+        self.trim();
+    }
+
+    /// Iterate over the events sent since the subscriber last read.
+    /// A subscription from another channel reads nothing.
+    pub fn read(&mut self, subscription: &Subscription<T>) -> vec_deque::Iter<'_, T> {
+        self.trim();
+        let end = self.head + self.events.len() as u64;
+
+        let start = match self.cursor_mut(subscription) {
+            Some(Some(cursor)) if *cursor <= end => std::mem::replace(cursor, end),
+            _ => end,
+        };
+
+        self.events.range((start - self.head) as usize..)
+    }
+
+    /// Number of events waiting for the subscriber
+    pub fn pending(&self, subscription: &Subscription<T>) -> usize {
+        let end = self.head + self.events.len() as u64;
+        let cursor = if subscription.channel == self.id { self.cursors.get(subscription.id) } else { None };
+        match cursor {
+            Some(Some(cursor)) if *cursor <= end => (end - cursor) as usize,
+            _ => 0,
+        }
+    }
+
+    // The subscriber's cursor, if the subscription belongs to this channel
+    fn cursor_mut(&mut self, subscription: &Subscription<T>) -> Option<&mut Option<u64>> {
+        if subscription.channel != self.id {
+            return None;
+        }
+        self.cursors.get_mut(subscription.id)
+    }
+
+    // Drop the events every subscriber has read
+    fn trim(&mut self) {
+        let oldest = self.cursors.iter()
+            .flatten()
+            .min()
+            .copied()
+            .unwrap_or(self.head + self.events.len() as u64);
+
+        while self.head < oldest && !self.events.is_empty() {
+            self.events.pop_front();
+            self.head += 1;
+        }
+    }
+}
+
+impl<T> Default for EventChannel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(channel: &mut EventChannel<u32>, subscription: &Subscription<u32>) -> Vec<u32> {
+        channel.read(subscription).copied().collect()
+    }
+
+    #[test]
+    fn reading_advances_the_cursor() {
+        let mut channel = EventChannel::new();
+        channel.send(0);
+        let subscription = channel.subscribe();
+        channel.send(1);
+        channel.send(2);
+
+        assert_eq!(channel.pending(&subscription), 2);
+        assert_eq!(read_all(&mut channel, &subscription), vec![1, 2]);
+        assert_eq!(channel.pending(&subscription), 0);
+        assert!(read_all(&mut channel, &subscription).is_empty());
+
+        channel.send(3);
+        assert_eq!(read_all(&mut channel, &subscription), vec![3]);
+    }
+
+    #[test]
+    fn subscribers_read_at_their_own_pace() {
+        let mut channel = EventChannel::new();
+        let fast = channel.subscribe();
+        let slow = channel.subscribe();
+        channel.send(1);
+        assert_eq!(read_all(&mut channel, &fast), vec![1]);
+        channel.send(2);
+        assert_eq!(read_all(&mut channel, &fast), vec![2]);
+        assert_eq!(read_all(&mut channel, &slow), vec![1, 2]);
+
+        channel.unsubscribe(slow);
+        channel.send(3);
+        assert_eq!(read_all(&mut channel, &fast), vec![3]);
+    }
+
+    #[test]
+    fn events_past_the_cap_are_dropped_oldest_first() {
+        let mut channel = EventChannel::new();
+        let subscription = channel.subscribe();
+        let sent = MAX_BUFFERED_EVENTS as u32 + 10;
+        for event in 0..sent {
+            channel.send(event);
+        }
+
+        assert_eq!(channel.pending(&subscription), MAX_BUFFERED_EVENTS);
+        let events = read_all(&mut channel, &subscription);
+        assert_eq!(events.len(), MAX_BUFFERED_EVENTS);
+        assert_eq!(events.first(), Some(&10));
+        assert_eq!(events.last(), Some(&(sent - 1)));
+    }
+
+    #[test]
+    fn subscriptions_from_another_channel_read_nothing() {
+        let mut first = EventChannel::new();
+        let mut second = EventChannel::new();
+        let from_first = first.subscribe();
+        let from_second = second.subscribe();
+        first.send(1);
+        second.send(2);
+
+        // Both subscriptions have the same cursor slot in their own channel
+        assert_eq!(second.pending(&from_first), 0);
+        assert!(read_all(&mut second, &from_first).is_empty());
+        second.unsubscribe(from_first);
+        assert_eq!(read_all(&mut second, &from_second), vec![2]);
+    }
+}
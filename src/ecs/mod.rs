@@ -1,10 +1,16 @@
 pub mod component;
 pub mod entity;
+pub mod event;
+pub mod query;
+pub mod schedule;
 pub mod system;
 pub mod world;
 
 // Re-export common components
 pub use self::component::Component;
 pub use self::entity::{Entity, EntityId};
+pub use self::event::{EventChannel, Subscription};
+pub use self::query::{Query, QueryFilter, With, Without};
+pub use self::schedule::{Schedule, Stage};
 pub use self::system::System;
 pub use self::world::World; 
\ No newline at end of file
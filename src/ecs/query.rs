@@ -0,0 +1,407 @@
+use std::marker::PhantomData;
+
+use super::component::{Component, ComponentType};
+use super::entity::{Entity, EntityId};
+use super::world::{ComponentStorage, EntitySlot};
+
+/// Something that can be fetched for each entity a query visits:
+/// `&T`, `&mut T`, `Option<&T>`, `Option<&mut T>`, `EntityId`, or a tuple of those.
+///
+/// ```ignore
+/// for (position, velocity) in world.query::<(&mut PositionComponent, &VelocityComponent)>() {
+///     position.x += velocity.dx;
+/// }
+/// ```
+///
+/// # Safety
+/// `World::query` relies on `access` to rule out two `&mut` to the same component.
+/// An implementation must record every component type `fetch` touches, marked as
+/// written if `fetch` hands out mutable access to it, and `matches` must only accept
+/// entities that `fetch` can fetch.
+pub unsafe trait Query {
+    /// What the query yields for each matching entity
+    type Item<'w>;
+    
+    /// Record which component types the query reads (`false`) or writes (`true`)
+    fn access(access: &mut Vec<(ComponentType, bool)>);
+    
+    /// Whether the entity has every component the query requires
+    fn matches(entity: &Entity) -> bool;
+    
+    /// Fetch the query's components for an entity that matches.
+    ///
+    /// # Safety
+    /// `storages` must point to the world's component storages, which must outlive `'w`,
+    /// the access recorded by `access` must not conflict, and each entity may only be
+    /// fetched once while the items are alive.
+    unsafe fn fetch<'w>(storages: *mut ComponentStorage, entity: &Entity) -> Self::Item<'w>;
+}
+
+/// Queries that only read components, so they can run on a shared `&World`
+///
+/// # Safety
+/// `fetch_ref` and `fetch` must never write to a component or hand out mutable access
+/// to one, and `access` must only record reads.
+pub unsafe trait ReadOnlyQuery: Query {
+    /// Fetch the query's components for an entity that matches, without write access.
+    ///
+    /// # Safety
+    /// `storages` must point to the world's component storages, which must outlive `'w`
+    /// and must not be written to while the items are alive.
+    unsafe fn fetch_ref<'w>(storages: *const ComponentStorage, entity: &Entity) -> Self::Item<'w>;
+}
+
+/// Narrows down which entities a query visits without fetching anything
+pub trait QueryFilter {
+    /// Whether the query should visit the entity
+    fn filter(entity: &Entity) -> bool;
+}
+
+/// Only visit entities that have a `T`
+pub struct With<T>(PhantomData<T>);
+
+/// Only visit entities that don't have a `T`
+pub struct Without<T>(PhantomData<T>);
+
+impl<T: Component> QueryFilter for With<T> {
+    fn filter(entity: &Entity) -> bool {
+        entity.has_component(&T::static_type())
+    }
+}
+
+impl<T: Component> QueryFilter for Without<T> {
+    fn filter(entity: &Entity) -> bool {
+        !entity.has_component(&T::static_type())
+    }
+}
+
+/// Slot index of the entity's `T` in `storage`, the storage for `T`, if it has one
+unsafe fn component_slot<T: Component>(storage: *const ComponentStorage, entity: &Entity) -> Option<usize> {
+    let index = entity.get_component_index(&T::static_type())?;
+    (index < (*storage).slots.len()).then_some(index)
+}
+
+unsafe fn read_component<'w, T: Component>(storages: *const ComponentStorage, entity: &Entity) -> Option<&'w T> {
+    let storage = storages.add(T::static_type().index());
+    let index = component_slot::<T>(storage, entity)?;
+    let slot = (*storage).slots.as_ptr().add(index);
+    (*slot).as_deref()?.as_any().downcast_ref::<T>()
+}
+
+unsafe fn write_component<'w, T: Component>(storages: *mut ComponentStorage, entity: &Entity) -> Option<&'w mut T> {
+    let storage = storages.add(T::static_type().index());
+    let index = component_slot::<T>(storage, entity)?;
+    let slot = (*storage).slots.as_mut_ptr().add(index);
+    (*slot).as_deref_mut()?.as_any_mut().downcast_mut::<T>()
+}
+
+unsafe impl<T: Component> Query for &T {
+    type Item<'w> = &'w T;
+    
+    fn access(access: &mut Vec<(ComponentType, bool)>) {
+        access.push((T::static_type(), false));
+    }
+    
+    fn matches(entity: &Entity) -> bool {
+        entity.has_component(&T::static_type())
+    }
+    
+    unsafe fn fetch<'w>(storages: *mut ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+        read_component::<T>(storages, entity).expect("entity matched a query without its component")
+    }
+}
+
+unsafe impl<T: Component> ReadOnlyQuery for &T {
+    unsafe fn fetch_ref<'w>(storages: *const ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+        read_component::<T>(storages, entity).expect("entity matched a query without its component")
+    }
+}
+
+unsafe impl<T: Component> Query for &mut T {
+    type Item<'w> = &'w mut T;
+    
+    fn access(access: &mut Vec<(ComponentType, bool)>) {
+        access.push((T::static_type(), true));
+    }
+    
+    fn matches(entity: &Entity) -> bool {
+        entity.has_component(&T::static_type())
+    }
+    
+    unsafe fn fetch<'w>(storages: *mut ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+        write_component::<T>(storages, entity).expect("entity matched a query without its component")
+    }
+}
+
+unsafe impl<T: Component> Query for Option<&T> {
+    type Item<'w> = Option<&'w T>;
+    
+    fn access(access: &mut Vec<(ComponentType, bool)>) {
+        access.push((T::static_type(), false));
+    }
+    
+    fn matches(_entity: &Entity) -> bool {
+        true
+    }
+    
+    unsafe fn fetch<'w>(storages: *mut ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+        read_component::<T>(storages, entity)
+    }
+}
+
+unsafe impl<T: Component> ReadOnlyQuery for Option<&T> {
+    unsafe fn fetch_ref<'w>(storages: *const ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+        read_component::<T>(storages, entity)
+    }
+}
+
+unsafe impl<T: Component> Query for Option<&mut T> {
+    type Item<'w> = Option<&'w mut T>;
+    
+    fn access(access: &mut Vec<(ComponentType, bool)>) {
+        access.push((T::static_type(), true));
+    }
+    
+    fn matches(_entity: &Entity) -> bool {
+        true
+    }
+    
+    unsafe fn fetch<'w>(storages: *mut ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+        write_component::<T>(storages, entity)
+    }
+}
+
+unsafe impl Query for EntityId {
+    type Item<'w> = EntityId;
+    
+    fn access(_access: &mut Vec<(ComponentType, bool)>) {}
+    
+    fn matches(_entity: &Entity) -> bool {
+        true
+    }
+    
+    unsafe fn fetch<'w>(_storages: *mut ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+        entity.id()
+    }
+}
+
+unsafe impl ReadOnlyQuery for EntityId {
+    unsafe fn fetch_ref<'w>(_storages: *const ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+        entity.id()
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($($term:ident),*) => {
+        unsafe impl<$($term: Query),*> Query for ($($term,)*) {
+            type Item<'w> = ($($term::Item<'w>,)*);
+            
+            #[allow(unused_variables)]
+            fn access(access: &mut Vec<(ComponentType, bool)>) {
+                $($term::access(access);)*
+            }
+            
+            #[allow(unused_variables)]
+            fn matches(entity: &Entity) -> bool {
+                true $(&& <$term as Query>::matches(entity))*
+            }
+            
+            #[allow(unused_variables, clippy::unused_unit)]
+            unsafe fn fetch<'w>(storages: *mut ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+                ($($term::fetch(storages, entity),)*)
+            }
+        }
+        
+        unsafe impl<$($term: ReadOnlyQuery),*> ReadOnlyQuery for ($($term,)*) {
+            #[allow(unused_variables, clippy::unused_unit)]
+            unsafe fn fetch_ref<'w>(storages: *const ComponentStorage, entity: &Entity) -> Self::Item<'w> {
+                ($($term::fetch_ref(storages, entity),)*)
+            }
+        }
+        
+        impl<$($term: QueryFilter),*> QueryFilter for ($($term,)*) {
+            #[allow(unused_variables)]
+            fn filter(entity: &Entity) -> bool {
+                true $(&& $term::filter(entity))*
+            }
+        }
+    };
+}
+
+impl_query_tuple!();
+impl_query_tuple!(A);
+impl_query_tuple!(A, B);
+impl_query_tuple!(A, B, C);
+impl_query_tuple!(A, B, C, D);
+impl_query_tuple!(A, B, C, D, E);
+impl_query_tuple!(A, B, C, D, E, G);
+
+/// Iterator over the entities matching a query, created by `World::query` and friends.
+/// Entities are visited in index order, so systems stay deterministic.
+pub struct QueryIter<'w, Q: Query, F: QueryFilter = ()> {
+    entities: std::slice::Iter<'w, EntitySlot>,
+    storages: *mut ComponentStorage,
+    _storages: PhantomData<&'w mut ComponentStorage>,
+    _query: PhantomData<fn() -> (Q, F)>,
+}
+
+impl<'w, Q: Query, F: QueryFilter> QueryIter<'w, Q, F> {
+    /// # Safety
+    /// `storages` must point to the storages of the world owning `entities` and stay
+    /// valid for `'w`, and nothing else may access them for `'w`.
+    pub(super) unsafe fn new(entities: &'w [EntitySlot], storages: *mut ComponentStorage) -> Self {
+        // Two terms writing the same component would hand out aliasing references
+        let mut access = Vec::new();
+        Q::access(&mut access);
+        for (i, &(component_type, write)) in access.iter().enumerate() {
+            for &(other_type, other_write) in &access[i + 1..] {
+                if component_type == other_type && (write || other_write) {
+                    panic!("query borrows {:?} mutably and accesses it again", component_type);
+                }
+            }
+        }
+        
+        Self {
+            entities: entities.iter(),
+            storages,
+            _storages: PhantomData,
+            _query: PhantomData,
+        }
+    }
+}
+
+impl<'w, Q: Query, F: QueryFilter> Iterator for QueryIter<'w, Q, F> {
+    type Item = Q::Item<'w>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = next_match::<Q, F>(&mut self.entities)?;
+        // Each entity is visited once and the access was checked in `new`
+        Some(unsafe { Q::fetch(self.storages, entity) })
+    }
+}
+
+/// Iterator over the entities matching a read-only query, created by `World::query_ref`
+/// and `World::query_ref_filtered`. Only reads through the storages, so any number can
+/// run at once on a shared world.
+pub struct QueryRefIter<'w, Q: ReadOnlyQuery, F: QueryFilter = ()> {
+    entities: std::slice::Iter<'w, EntitySlot>,
+    storages: *const ComponentStorage,
+    _storages: PhantomData<&'w ComponentStorage>,
+    _query: PhantomData<fn() -> (Q, F)>,
+}
+
+impl<'w, Q: ReadOnlyQuery, F: QueryFilter> QueryRefIter<'w, Q, F> {
+    pub(super) fn new(entities: &'w [EntitySlot], storages: &'w [ComponentStorage]) -> Self {
+        Self {
+            entities: entities.iter(),
+            storages: storages.as_ptr(),
+            _storages: PhantomData,
+            _query: PhantomData,
+        }
+    }
+}
+
+impl<'w, Q: ReadOnlyQuery, F: QueryFilter> Iterator for QueryRefIter<'w, Q, F> {
+    type Item = Q::Item<'w>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        let entity = next_match::<Q, F>(&mut self.entities)?;
+        // The storages are borrowed shared for 'w, and read-only queries never write
+        Some(unsafe { Q::fetch_ref(self.storages, entity) })
+    }
+}
+
+/// Advance to the next live entity that matches the query and passes the filter
+fn next_match<'w, Q: Query, F: QueryFilter>(entities: &mut std::slice::Iter<'w, EntitySlot>) -> Option<&'w Entity> {
+    entities
+        .filter_map(|slot| slot.entity.as_ref())
+        .find(|entity| Q::matches(entity) && F::filter(entity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::component::{FoodComponent, PositionComponent, VelocityComponent};
+    use crate::ecs::World;
+
+    fn velocity(dx: f32) -> VelocityComponent {
+        VelocityComponent { dx, dy: 0.0, speed: 1.0, direction: 0.0 }
+    }
+
+    // Two entities with a position, only the first also moving
+    fn world_with_two_entities() -> (World, EntityId, EntityId) {
+        let mut world = World::new();
+        let moving = world.create_entity();
+        world.add_component(moving, PositionComponent { x: 1.0, y: 0.0 });
+        world.add_component(moving, velocity(2.0));
+        let still = world.create_entity();
+        world.add_component(still, PositionComponent { x: 5.0, y: 0.0 });
+        (world, moving, still)
+    }
+
+    #[test]
+    #[should_panic(expected = "mutably")]
+    fn duplicate_mutable_term_panics() {
+        let (mut world, _, _) = world_with_two_entities();
+        let _ = world.query::<(&mut PositionComponent, &mut PositionComponent)>();
+    }
+
+    #[test]
+    #[should_panic(expected = "mutably")]
+    fn mutable_and_shared_term_for_the_same_component_panics() {
+        let (mut world, _, _) = world_with_two_entities();
+        let _ = world.query::<(&PositionComponent, Option<&mut PositionComponent>)>();
+    }
+
+    #[test]
+    fn shared_terms_for_the_same_component_are_allowed() {
+        let (world, _, _) = world_with_two_entities();
+        assert_eq!(world.query_ref::<(&PositionComponent, &PositionComponent)>().count(), 2);
+    }
+
+    #[test]
+    fn required_terms_skip_entities_without_the_component() {
+        let (mut world, moving, _) = world_with_two_entities();
+        for (position, velocity) in world.query::<(&mut PositionComponent, &VelocityComponent)>() {
+            position.x += velocity.dx;
+        }
+
+        let ids: Vec<EntityId> = world.query_ref::<(EntityId, &VelocityComponent)>().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![moving]);
+        assert_eq!(world.get_component::<PositionComponent>(moving).unwrap().x, 3.0);
+    }
+
+    #[test]
+    fn option_terms_visit_every_entity() {
+        let (mut world, moving, still) = world_with_two_entities();
+        let seen: Vec<(EntityId, Option<f32>)> = world.query_ref::<(EntityId, Option<&VelocityComponent>)>()
+            .map(|(id, velocity)| (id, velocity.map(|velocity| velocity.dx)))
+            .collect();
+        assert_eq!(seen, vec![(moving, Some(2.0)), (still, None)]);
+
+        for (_, velocity) in world.query::<(&PositionComponent, Option<&mut VelocityComponent>)>() {
+            if let Some(velocity) = velocity {
+                velocity.dx = 4.0;
+            }
+        }
+        assert_eq!(world.get_component::<VelocityComponent>(moving).unwrap().dx, 4.0);
+    }
+
+    #[test]
+    fn with_and_without_filter_entities() {
+        let (mut world, moving, still) = world_with_two_entities();
+        let food = world.create_entity();
+        world.add_component(food, FoodComponent::new(10.0));
+
+        let with: Vec<EntityId> = world.query_ref_filtered::<EntityId, With<VelocityComponent>>().collect();
+        assert_eq!(with, vec![moving]);
+
+        let without: Vec<EntityId> = world.query_ref_filtered::<EntityId, Without<VelocityComponent>>().collect();
+        assert_eq!(without, vec![still, food]);
+
+        let both: Vec<EntityId> = world
+            .query_filtered::<EntityId, (With<PositionComponent>, Without<VelocityComponent>)>()
+            .collect();
+        assert_eq!(both, vec![still]);
+    }
+}
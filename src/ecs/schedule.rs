@@ -0,0 +1,309 @@
+use log::warn;
+use std::time::{Duration, Instant};
+
+use super::system::System;
+use super::world::World;
+
+/// Stages a world update runs through, in this order.
+/// Every system belongs to exactly one stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Input,
+    Behavior,
+    Physics,
+    Pheromone,
+    Cleanup,
+}
+
+impl Stage {
+    /// Every stage, in the order they run
+    pub const ALL: [Stage; 5] = [
+        Stage::Input,
+        Stage::Behavior,
+        Stage::Physics,
+        Stage::Pheromone,
+        Stage::Cleanup,
+    ];
+}
+
+/// How long a system has been taking
+#[derive(Debug, Clone, Default)]
+pub struct SystemStats {
+    pub runs: u64,
+    pub last: Duration,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl SystemStats {
+    fn record(&mut self, elapsed: Duration) {
+        self.runs += 1;
+        self.last = elapsed;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+    
+    /// Mean time per run
+    pub fn average(&self) -> Duration {
+        if self.runs == 0 {
+            Duration::ZERO
+        } else {
+            self.total.div_f64(self.runs as f64)
+        }
+    }
+}
+
+/// What the schedule knows about one of its systems
+pub struct SystemInfo<'a> {
+    pub name: &'a str,
+    pub stage: Stage,
+    pub enabled: bool,
+    pub stats: &'a SystemStats,
+}
+
+struct ScheduledSystem {
+    system: Box<dyn System>,
+    enabled: bool,
+    stats: SystemStats,
+}
+
+/// Decides in which order a world's systems run and keeps track of their cost.
+///
+/// Systems run stage by stage. Within a stage, `System::after` and `System::before`
+/// constraints are honored and otherwise systems keep the order they were added in.
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<ScheduledSystem>,
+    // Indices into `systems` in the order they run
+    order: Vec<usize>,
+    dirty: bool,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add a system, enabled
+    pub fn add_system(&mut self, system: Box<dyn System>) {
+        self.systems.push(ScheduledSystem {
+            system,
+            enabled: true,
+            stats: SystemStats::default(),
+        });
+        self.dirty = true;
+    }
+    
+    /// Run every enabled system once
+    pub fn run(&mut self, world: &mut World, delta_time: f32) {
+        if self.dirty {
+            self.order = self.build_order();
+            self.dirty = false;
+        }
+        
+        for &index in &self.order {
+            let entry = &mut self.systems[index];
+            if !entry.enabled {
+                continue;
+            }
+            
+            let start = Instant::now();
+            entry.system.update(world, delta_time);
+            entry.stats.record(start.elapsed());
+        }
+    }
+    
+    /// Turn a system on or off by name. Returns false if there is no such system.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for entry in self.systems.iter_mut().filter(|entry| entry.system.name() == name) {
+            entry.enabled = enabled;
+            found = true;
+        }
+        found
+    }
+    
+    /// Whether the named system is enabled, or None if there is no such system
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.systems.iter()
+            .find(|entry| entry.system.name() == name)
+            .map(|entry| entry.enabled)
+    }
+    
+    /// All systems in the order they run, with their timing stats
+    pub fn systems(&mut self) -> Vec<SystemInfo<'_>> {
+        if self.dirty {
+            self.order = self.build_order();
+            self.dirty = false;
+        }
+        
+        self.order.iter()
+            .map(|&index| {
+                let entry = &self.systems[index];
+                SystemInfo {
+                    name: entry.system.name(),
+                    stage: entry.system.stage(),
+                    enabled: entry.enabled,
+                    stats: &entry.stats,
+                }
+            })
+            .collect()
+    }
+    
+    /// Sort the systems by stage, then by their ordering constraints within the stage.
+    /// Panics if the constraints within a stage form a cycle.
+    fn build_order(&self) -> Vec<usize> {
+        self.check_cross_stage_constraints();
+        
+        let mut order = Vec::with_capacity(self.systems.len());
+        
+        for stage in Stage::ALL {
+            let members: Vec<usize> = (0..self.systems.len())
+                .filter(|&i| self.systems[i].system.stage() == stage)
+                .collect();
+            
+            // edges[a] holds the systems that have to wait for `a`
+            let mut edges: Vec<Vec<usize>> = vec![Vec::new(); self.systems.len()];
+            let mut waiting_on = vec![0usize; self.systems.len()];
+            
+            for &i in &members {
+                let system = &self.systems[i].system;
+                for &j in &members {
+                    let other = self.systems[j].system.name();
+                    if i == j {
+                        continue;
+                    }
+                    if system.after().contains(&other) || self.systems[j].system.before().contains(&system.name()) {
+                        edges[j].push(i);
+                        waiting_on[i] += 1;
+                    }
+                }
+            }
+            
+            // Kahn's algorithm, always taking the earliest added system that is ready
+            let mut ready: Vec<usize> = members.iter().copied().filter(|&i| waiting_on[i] == 0).collect();
+            let mut placed = 0;
+            while !ready.is_empty() {
+                let next = ready.remove(0);
+                order.push(next);
+                placed += 1;
+                
+                for &after in &edges[next] {
+                    waiting_on[after] -= 1;
+                    if waiting_on[after] == 0 {
+                        let position = ready.partition_point(|&i| i < after);
+                        ready.insert(position, after);
+                    }
+                }
+            }
+            
+            if placed != members.len() {
+                let stuck: Vec<&str> = members.iter()
+                    .filter(|&&i| waiting_on[i] > 0)
+                    .map(|&i| self.systems[i].system.name())
+                    .collect();
+                panic!("System ordering constraints form a cycle in stage {:?}: {}", stage, stuck.join(", "));
+            }
+        }
+        
+        order
+    }
+    
+    // Constraints between stages can't be honored by reordering, only point out the ones
+    // that contradict the stage order
+    fn check_cross_stage_constraints(&self) {
+        for entry in &self.systems {
+            let system = &entry.system;
+            for other in &self.systems {
+                let other = &other.system;
+                if system.after().contains(&other.name()) && other.stage() > system.stage() {
+                    warn!(
+                        "{} wants to run after {}, but its stage {:?} runs before {:?}",
+                        system.name(), other.name(), system.stage(), other.stage()
+                    );
+                }
+                if system.before().contains(&other.name()) && other.stage() < system.stage() {
+                    warn!(
+                        "{} wants to run before {}, but its stage {:?} runs after {:?}",
+                        system.name(), other.name(), system.stage(), other.stage()
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Named {
+        name: &'static str,
+        stage: Stage,
+        after: &'static [&'static str],
+        before: &'static [&'static str],
+    }
+
+    impl System for Named {
+        fn update(&mut self, _world: &mut World, _delta_time: f32) {}
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn stage(&self) -> Stage {
+            self.stage
+        }
+
+        fn after(&self) -> &[&str] {
+            self.after
+        }
+
+        fn before(&self) -> &[&str] {
+            self.before
+        }
+    }
+
+    fn system(name: &'static str, stage: Stage) -> Box<dyn System> {
+        Box::new(Named { name, stage, after: &[], before: &[] })
+    }
+
+    fn order(schedule: &mut Schedule) -> Vec<&str> {
+        schedule.systems().iter().map(|info| info.name).collect()
+    }
+
+    #[test]
+    fn stages_run_in_order_and_systems_keep_the_order_they_were_added_in() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("cleanup", Stage::Cleanup));
+        schedule.add_system(system("second", Stage::Behavior));
+        schedule.add_system(system("input", Stage::Input));
+        schedule.add_system(system("third", Stage::Behavior));
+
+        assert_eq!(order(&mut schedule), ["input", "second", "third", "cleanup"]);
+    }
+
+    #[test]
+    fn after_and_before_constraints_reorder_a_stage() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Box::new(Named { name: "a", stage: Stage::Behavior, after: &["c"], before: &[] }));
+        schedule.add_system(system("b", Stage::Behavior));
+        schedule.add_system(system("c", Stage::Behavior));
+        schedule.add_system(Box::new(Named { name: "d", stage: Stage::Behavior, after: &[], before: &["b"] }));
+
+        // Only c and d are ready at first. Once c has run, a was added before d
+        // so it goes next, and b has to wait for d
+        assert_eq!(order(&mut schedule), ["c", "a", "d", "b"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "form a cycle in stage Behavior: a, b")]
+    fn a_cycle_panics() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Box::new(Named { name: "a", stage: Stage::Behavior, after: &["b"], before: &[] }));
+        schedule.add_system(Box::new(Named { name: "b", stage: Stage::Behavior, after: &["a"], before: &[] }));
+        schedule.add_system(system("c", Stage::Behavior));
+
+        schedule.systems();
+    }
+}
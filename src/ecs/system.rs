@@ -1,9 +1,21 @@
 use crate::ecs::world::World;
 use crate::ecs::entity::EntityId;
-use crate::ecs::component::AntState;
-use sfml::graphics::{RenderWindow, CircleShape, RectangleShape, Color, Transformable, Shape, RenderTarget};
-use sfml::system::Vector2f;
+use crate::ecs::event::{EventChannel, Subscription};
+use crate::ecs::schedule::Stage;
+use crate::ecs::component::{
+    AntStateComponent, ColliderComponent, CollisionLayer, FoodComponent, HealthComponent, PheromoneComponent,
+    PositionComponent, VelocityComponent,
+};
+use crate::ant::{Ant, AntParams, ColonySite};
+use crate::colony::Colony;
+use crate::environment::{CellType, Environment};
+use crate::events::SimEvent;
+use crate::math::Vector2f;
+use crate::pheromone::PheromoneType;
+use std::collections::{HashMap, HashSet};
 use std::f32::consts::PI;
+use rand::Rng;
+use log::{debug, info};
 
 /// Trait for all systems
 pub trait System {
@@ -12,9 +24,38 @@ pub trait System {
     
     /// Get the system name
     fn name(&self) -> &str;
+    
+    /// Stage of the world update the system runs in
+    fn stage(&self) -> Stage {
+        Stage::Behavior
+    }
+    
+    /// Names of systems in the same stage that have to run before this one
+    fn after(&self) -> &[&str] {
+        &[]
+    }
+    
+    /// Names of systems in the same stage that have to run after this one
+    fn before(&self) -> &[&str] {
+        &[]
+    }
+}
+
+/// Snapshot of every colony's ant tuning, for systems that act on ants while
+/// the world is borrowed
+fn colony_params(world: &World) -> HashMap<EntityId, AntParams> {
+    world.query_ref::<(EntityId, &Colony)>()
+        .map(|(id, colony)| (id, colony.get_params().clone()))
+        .collect()
+}
+
+/// The tuning an ant of `colony_id` follows; ants without a colony use the defaults
+fn params_for(colony_params: &HashMap<EntityId, AntParams>, colony_id: Option<EntityId>) -> &AntParams {
+    colony_id.and_then(|colony_id| colony_params.get(&colony_id)).unwrap_or(&AntParams::DEFAULT)
 }
 
-/// Basic movement system that updates positions based on velocities
+/// Movement system that moves entities along their velocity,
+/// bouncing them off walls and the edges of the environment
 pub struct MovementSystem;
 
 impl MovementSystem {
@@ -25,380 +66,695 @@ impl MovementSystem {
 
 impl System for MovementSystem {
     fn update(&mut self, world: &mut World, delta_time: f32) {
-        // Get all entities with both position and velocity components
-        let entity_ids = world.get_entities_with_components(&[
-            super::component::ComponentType::Position,
-            super::component::ComponentType::Velocity,
-        ]);
-        
-        for entity_id in entity_ids {
-            // First, collect all the data we need
-            let position_opt = world.get_component::<super::component::PositionComponent>(
-                entity_id,
-                super::component::ComponentType::Position,
-            );
+        // Walls and edges come from the environment, so take it out of the world
+        // while we move things around
+        let mut environment = match world.remove_resource::<Environment>() {
+            Some(environment) => environment,
+            None => return,
+        };
+        
+        let colony_params = colony_params(world);
+        
+        for (position, velocity, ant_state) in world.query::<(
+            &mut PositionComponent,
+            &mut VelocityComponent,
+            Option<&mut AntStateComponent>,
+        )>() {
+            // Everything else that moves bounces like an ant without a colony
+            let params = params_for(&colony_params, ant_state.as_deref().and_then(|state| state.colony_id));
             
-            let velocity_opt = world.get_component::<super::component::VelocityComponent>(
-                entity_id,
-                super::component::ComponentType::Velocity,
-            );
+            // Calculate next position
+            let next_x = position.x + velocity.dx * velocity.speed * delta_time;
+            let next_y = position.y + velocity.dy * velocity.speed * delta_time;
             
-            // Calculate the new position
-            if let (Some(position), Some(velocity)) = (position_opt, velocity_opt) {
-                let new_x = position.x + velocity.dx * velocity.speed * delta_time;
-                let new_y = position.y + velocity.dy * velocity.speed * delta_time;
+            // Check for wall collisions
+            let (grid_x, grid_y) = environment.screen_to_grid(next_x, next_y);
+            if environment.get_cell(grid_x, grid_y) == CellType::Wall {
+                // Hit a wall, bounce off in a realistic way
+                
+                // Check which direction we need to bounce (horizontal or vertical wall)
+                // Try checking horizontal and vertical adjacent cells to determine wall orientation
+                let (current_grid_x, current_grid_y) = environment.screen_to_grid(position.x, position.y);
+                
+                let horizontal_wall = current_grid_x != grid_x && 
+                                     environment.get_cell(grid_x, current_grid_y) == CellType::Wall;
                 
-                // Now update the position
-                if let Some(position) = world.get_component_mut::<super::component::PositionComponent>(
-                    entity_id,
-                    super::component::ComponentType::Position,
-                ) {
-                    position.x = new_x;
-                    position.y = new_y;
+                let vertical_wall = current_grid_y != grid_y && 
+                                   environment.get_cell(current_grid_x, grid_y) == CellType::Wall;
+                
+                if horizontal_wall {
+                    // Bounce horizontally
+                    velocity.direction = PI - velocity.direction;
+                } else if vertical_wall {
+                    // Bounce vertically
+                    velocity.direction = -velocity.direction;
+                } else {
+                    // Corner or diagonal collision, reverse direction
+                    velocity.direction = (velocity.direction + PI) % (2.0 * PI);
                 }
+                
+                // Add a small random variation to prevent entities from getting stuck
+                velocity.direction += (environment.rng().gen::<f32>() - 0.5) * params.wall_bounce_jitter;
+                
+                // Move in the new direction
+                position.x += velocity.direction.cos() * velocity.speed * delta_time;
+                position.y += velocity.direction.sin() * velocity.speed * delta_time;
+            } else {
+                // No wall, proceed with movement
+                position.x = next_x;
+                position.y = next_y;
             }
+            
+            // Boundary check - bounce off edges
+            let margin = params.edge_margin;
+            let env_width = environment.get_width() as f32;
+            let env_height = environment.get_height() as f32;
+            
+            let mut did_bounce = false;
+            
+            if position.x < margin {
+                position.x = margin;
+                velocity.direction = PI - velocity.direction;
+                did_bounce = true;
+            } else if position.x > env_width - margin {
+                position.x = env_width - margin;
+                velocity.direction = PI - velocity.direction;
+                did_bounce = true;
+            }
+            
+            if position.y < margin {
+                position.y = margin;
+                velocity.direction = -velocity.direction;
+                did_bounce = true;
+            } else if position.y > env_height - margin {
+                position.y = env_height - margin;
+                velocity.direction = -velocity.direction;
+                did_bounce = true;
+            }
+            
+            // Add significant randomness when bouncing off edges to prevent circling
+            if did_bounce {
+                // Add a larger random variation (up to +/- 45 degrees by default)
+                velocity.direction += (environment.rng().gen::<f32>() - 0.5) * params.edge_bounce_turn;
+                
+                // Move slightly away from edge to prevent getting stuck
+                let bounce_step = params.edge_bounce_step;
+                position.x += velocity.direction.cos() * bounce_step;
+                position.y += velocity.direction.sin() * bounce_step;
+                
+                // Ants briefly ignore pheromones after bouncing
+                if let Some(ant_state) = ant_state {
+                    ant_state.ignore_pheromones_timer = params.ignore_after_bounce;
+                }
+            }
+            
+            velocity.dx = velocity.direction.cos();
+            velocity.dy = velocity.direction.sin();
         }
+        
+        world.add_resource(environment);
     }
     
     fn name(&self) -> &str {
         "MovementSystem"
     }
+    
+    fn stage(&self) -> Stage {
+        Stage::Physics
+    }
+}
+
+/// System that handles ant behavior: pheromone following, food pickup and delivery.
+/// The actual decisions live in `ant::Ant`, which works on the components of one ant.
+pub struct AntBehaviorSystem;
+
+impl AntBehaviorSystem {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl System for AntBehaviorSystem {
+    fn update(&mut self, world: &mut World, delta_time: f32) {
+        // The environment owns the pheromones and the simulation's random number
+        // generator, so ants can't make decisions without it
+        let mut environment = match world.remove_resource::<Environment>() {
+            Some(environment) => environment,
+            None => return,
+        };
+        
+        // Snapshot where the colonies are so ants know where to deliver food
+        let colonies: Vec<ColonySite> = world.query_ref::<(EntityId, &Colony)>()
+            .map(|(id, colony)| ColonySite {
+                id,
+                position: colony.get_position(),
+                radius: colony.get_radius(),
+                steering: colony.get_steering(),
+                params: colony.get_params().clone(),
+            })
+            .collect();
+        
+        // Take the event channel out too, ants publish to it while their components are borrowed
+        let mut events = world.remove_resource::<EventChannel<SimEvent>>().unwrap_or_default();
+        
+        let mut deliveries: Vec<(EntityId, EntityId, f32)> = Vec::new();
+        
+        for (entity_id, position, velocity, ant_state) in world.query::<(
+            EntityId,
+            &mut PositionComponent,
+            &mut VelocityComponent,
+            &mut AntStateComponent,
+        )>() {
+            // Ants without a colony use the default tuning
+            let params = ant_state.colony_id
+                .and_then(|colony_id| colonies.iter().find(|colony| colony.id == colony_id))
+                .map_or(&AntParams::DEFAULT, |colony| &colony.params);
+            let mut ant = Ant::new(entity_id, position, velocity, ant_state, params);
+            if let Some((colony_id, amount)) = ant.update(delta_time, &mut environment, &colonies, &mut events) {
+                deliveries.push((entity_id, colony_id, amount));
+            }
+        }
+        
+        // Hand the delivered food to the colonies, as much as each ant picked up
+        for (ant_id, colony_id, amount) in deliveries {
+            if let Some(colony) = world.get_component_mut::<Colony>(colony_id) {
+                colony.add_food(amount);
+                events.send(SimEvent::FoodDelivered { ant: ant_id, colony: colony_id, amount });
+            }
+        }
+        
+        world.add_resource(events);
+        world.add_resource(environment);
+    }
+    
+    fn name(&self) -> &str {
+        "AntBehaviorSystem"
+    }
+    
+    fn after(&self) -> &[&str] {
+        // Newly spawned ants get to act on their first tick
+        &["ColonySystem"]
+    }
+}
+
+/// System that keeps colonies up to date: counts their ants and spawns new ones
+#[derive(Default)]
+pub struct ColonySystem;
+
+impl ColonySystem {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl System for ColonySystem {
+    fn update(&mut self, world: &mut World, _delta_time: f32) {
+        // Count how many ants belong to each colony
+        let mut ant_counts: HashMap<EntityId, usize> = HashMap::new();
+        for ant_state in world.query_ref::<&AntStateComponent>() {
+            if let Some(colony_id) = ant_state.colony_id {
+                *ant_counts.entry(colony_id).or_insert(0) += 1;
+            }
+        }
+        
+        // Let each colony decide how many ants to raise
+        let spawns: Vec<(EntityId, Vector2f, usize)> = world.query::<(EntityId, &mut Colony)>()
+            .map(|(colony_id, colony)| {
+                colony.set_ant_count(ant_counts.get(&colony_id).copied().unwrap_or(0));
+                colony.update();
+                (colony_id, colony.get_position(), colony.take_pending_spawns())
+            })
+            .collect();
+        
+        for (colony_id, position, count) in spawns {
+            for _ in 0..count {
+                crate::ant::spawn_ant(world, position.x, position.y, Some(colony_id));
+            }
+            
+            if let Some(colony) = world.get_component_mut::<Colony>(colony_id) {
+                colony.set_ant_count(colony.get_ant_count() + count);
+            }
+        }
+    }
+    
+    fn name(&self) -> &str {
+        "ColonySystem"
+    }
 }
 
-/// Rendering system that draws all entities with appearance and position components
-pub struct RenderingSystem<'a> {
-    window: &'a mut RenderWindow,
+/// System that advances the environment: pheromone evaporation and the like
+#[derive(Default)]
+pub struct EnvironmentSystem;
+
+impl EnvironmentSystem {
+    pub fn new() -> Self {
+        Self
+    }
 }
 
-impl<'a> RenderingSystem<'a> {
-    pub fn new(window: &'a mut RenderWindow) -> Self {
-        Self { window }
+impl System for EnvironmentSystem {
+    fn update(&mut self, world: &mut World, delta_time: f32) {
+        if let Some(environment) = world.get_resource_mut::<Environment>() {
+            environment.update(delta_time);
+        }
+    }
+    
+    fn name(&self) -> &str {
+        "EnvironmentSystem"
+    }
+    
+    fn stage(&self) -> Stage {
+        Stage::Pheromone
+    }
+}
+
+/// Two colliders that overlapped at the end of the last physics stage
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    pub a: EntityId,
+    pub a_layer: CollisionLayer,
+    pub b: EntityId,
+    pub b_layer: CollisionLayer,
+}
+
+/// Resource holding every contact found by the CollisionSystem
+#[derive(Default)]
+pub struct Contacts {
+    contacts: Vec<Contact>,
+}
+
+impl Contacts {
+    /// All contacts, in entity order
+    pub fn all(&self) -> &[Contact] {
+        &self.contacts
+    }
+    
+    /// Contacts between an entity on `first` and one on `second`, as (first, second) pairs
+    pub fn between(&self, first: CollisionLayer, second: CollisionLayer) -> impl Iterator<Item = (EntityId, EntityId)> + '_ {
+        self.contacts.iter().filter_map(move |contact| {
+            if contact.a_layer == first && contact.b_layer == second {
+                Some((contact.a, contact.b))
+            } else if contact.b_layer == first && contact.a_layer == second {
+                Some((contact.b, contact.a))
+            } else {
+                None
+            }
+        })
     }
 }
 
-impl<'a> System for RenderingSystem<'a> {
+/// System that finds overlapping circle colliders and stores them in the `Contacts` resource.
+/// Colliders are bucketed in a grid so only nearby pairs are tested.
+#[derive(Default)]
+pub struct CollisionSystem;
+
+impl CollisionSystem {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl System for CollisionSystem {
     fn update(&mut self, world: &mut World, _delta_time: f32) {
-        // Get all entities with both position and appearance components
-        let entity_ids = world.get_entities_with_components(&[
-            super::component::ComponentType::Position,
-            super::component::ComponentType::Appearance,
-        ]);
-        
-        for entity_id in entity_ids {
-            // Get position component
-            if let Some(position) = world.get_component::<super::component::PositionComponent>(
-                entity_id,
-                super::component::ComponentType::Position,
-            ) {
-                // Get appearance component
-                if let Some(appearance) = world.get_component::<super::component::AppearanceComponent>(
-                    entity_id,
-                    super::component::ComponentType::Appearance,
-                ) {
-                    // Draw the entity based on its appearance
-                    match appearance.shape_type {
-                        super::component::ShapeType::Circle => {
-                            let mut shape = CircleShape::new(appearance.radius, 32);
-                            shape.set_position(Vector2f::new(
-                                position.x - appearance.radius,
-                                position.y - appearance.radius,
-                            ));
-                            shape.set_fill_color(Color::rgb(
-                                appearance.color.0,
-                                appearance.color.1,
-                                appearance.color.2,
-                            ));
-                            self.window.draw(&shape);
+        let colliders: Vec<(EntityId, f32, f32, f32, CollisionLayer)> = world
+            .query_ref::<(EntityId, &PositionComponent, &ColliderComponent)>()
+            .map(|(id, position, collider)| (id, position.x, position.y, collider.radius, collider.layer))
+            .collect();
+        
+        // Cells as big as the largest collider, so touching colliders are at most one cell apart
+        let cell_size = colliders.iter()
+            .map(|&(_, _, _, radius, _)| radius * 2.0)
+            .fold(1.0, f32::max);
+        let cell_of = |x: f32, y: f32| ((x / cell_size).floor() as i32, (y / cell_size).floor() as i32);
+        
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (i, &(_, x, y, _, _)) in colliders.iter().enumerate() {
+            cells.entry(cell_of(x, y)).or_default().push(i);
+        }
+        
+        let mut contacts = Vec::new();
+        for (i, &(a, ax, ay, a_radius, a_layer)) in colliders.iter().enumerate() {
+            let (cell_x, cell_y) = cell_of(ax, ay);
+            for neighbor_y in cell_y - 1..=cell_y + 1 {
+                for neighbor_x in cell_x - 1..=cell_x + 1 {
+                    let Some(bucket) = cells.get(&(neighbor_x, neighbor_y)) else {
+                        continue;
+                    };
+                    
+                    // Only look at later colliders so every pair is found once
+                    for &j in bucket.iter().filter(|&&j| j > i) {
+                        let (b, bx, by, b_radius, b_layer) = colliders[j];
+                        if !a_layer.touches(b_layer) {
+                            continue;
                         }
-                        super::component::ShapeType::Rectangle => {
-                            let mut shape = RectangleShape::new();
-                            shape.set_size(Vector2f::new(appearance.width, appearance.height));
-                            shape.set_position(Vector2f::new(
-                                position.x - appearance.width / 2.0,
-                                position.y - appearance.height / 2.0,
-                            ));
-                            shape.set_fill_color(Color::rgb(
-                                appearance.color.0,
-                                appearance.color.1,
-                                appearance.color.2,
-                            ));
-                            self.window.draw(&shape);
+                        
+                        let (dx, dy) = (bx - ax, by - ay);
+                        let reach = a_radius + b_radius;
+                        if dx * dx + dy * dy <= reach * reach {
+                            contacts.push((i, j, Contact { a, a_layer, b, b_layer }));
                         }
                     }
                 }
             }
         }
+        
+        // Buckets are visited in a fixed order per collider; sort so contacts come out in entity order
+        contacts.sort_by_key(|&(i, j, _)| (i, j));
+        let contacts = contacts.into_iter().map(|(_, _, contact)| contact).collect();
+        
+        world.add_resource(Contacts { contacts });
     }
     
     fn name(&self) -> &str {
-        "RenderingSystem"
+        "CollisionSystem"
+    }
+    
+    fn stage(&self) -> Stage {
+        Stage::Physics
+    }
+    
+    fn after(&self) -> &[&str] {
+        // Contacts are found where entities ended up this tick
+        &["MovementSystem"]
     }
 }
 
-/// System that handles ant behavior
-pub struct AntBehaviorSystem {
-    // We'll no longer store window dimensions directly, as we'll get them from environment
-}
+/// System that lets ants take food from food source entities they touch
+/// and removes sources once they are empty
+#[derive(Default)]
+pub struct FoodSystem;
 
-impl AntBehaviorSystem {
+impl FoodSystem {
     pub fn new() -> Self {
-        Self {}
+        Self
+    }
+}
+
+impl System for FoodSystem {
+    fn update(&mut self, world: &mut World, _delta_time: f32) {
+        // The first food source each ant touches
+        let mut touching: HashMap<EntityId, EntityId> = HashMap::new();
+        if let Some(contacts) = world.get_resource::<Contacts>() {
+            for (ant_id, food_id) in contacts.between(CollisionLayer::Ant, CollisionLayer::Food) {
+                touching.entry(ant_id).or_insert(food_id);
+            }
+        }
+        if touching.is_empty() {
+            return;
+        }
+        
+        let mut remaining: HashMap<EntityId, f32> = world.query_ref::<(EntityId, &FoodComponent)>()
+            .map(|(id, food)| (id, food.remaining))
+            .collect();
+        
+        let colony_params = colony_params(world);
+        
+        let mut environment = match world.remove_resource::<Environment>() {
+            Some(environment) => environment,
+            None => return,
+        };
+        let mut events = world.remove_resource::<EventChannel<SimEvent>>().unwrap_or_default();
+        
+        // Ants take the same load from a source as from a food cell
+        let amount = environment.get_food_config().amount_per_pickup;
+        
+        for (entity_id, position, velocity, ant_state) in world.query::<(
+            EntityId,
+            &mut PositionComponent,
+            &mut VelocityComponent,
+            &mut AntStateComponent,
+        )>() {
+            if ant_state.carrying_food {
+                continue;
+            }
+            let Some(food_left) = touching.get(&entity_id).and_then(|food_id| remaining.get_mut(food_id)) else {
+                continue;
+            };
+            if *food_left < amount {
+                continue;
+            }
+            
+            *food_left -= amount;
+            let params = params_for(&colony_params, ant_state.colony_id);
+            Ant::new(entity_id, position, velocity, ant_state, params).pick_up_food(amount, &mut environment, &mut events);
+        }
+        
+        // Write back what is left and clear out the empty sources
+        let mut depleted = Vec::new();
+        for (food_id, position, food) in world.query::<(EntityId, &PositionComponent, &mut FoodComponent)>() {
+            if let Some(&food_left) = remaining.get(&food_id) {
+                food.take(food.remaining - food_left);
+            }
+            if food.remaining < amount {
+                depleted.push((food_id, Vector2f::new(position.x, position.y)));
+            }
+        }
+        
+        for (food_id, position) in depleted {
+            world.remove_entity(food_id);
+            events.send(SimEvent::FoodSourceDepleted { source: food_id, position });
+        }
+        
+        world.add_resource(events);
+        world.add_resource(environment);
     }
     
-    // Calculate direction to target position
-    fn direction_to(&self, from_x: f32, from_y: f32, to_x: f32, to_y: f32) -> f32 {
-        let dx = to_x - from_x;
-        let dy = to_y - from_y;
-        dy.atan2(dx)
+    fn name(&self) -> &str {
+        "FoodSystem"
+    }
+    
+    fn before(&self) -> &[&str] {
+        // Ants that just picked up food head home on the same tick
+        &["AntBehaviorSystem"]
     }
 }
 
-impl System for AntBehaviorSystem {
+/// System that drains health over time and removes entities that run out.
+/// Ants recover at their own colony.
+#[derive(Default)]
+pub struct HealthSystem;
+
+impl HealthSystem {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl System for HealthSystem {
     fn update(&mut self, world: &mut World, delta_time: f32) {
-        // Get all entities with ant state, position, and velocity components
-        let entity_ids = world.get_entities_with_components(&[
-            super::component::ComponentType::AntState,
-            super::component::ComponentType::Position,
-            super::component::ComponentType::Velocity,
-        ]);
-        
-        // Get environment dimensions for boundary checking
-        let (env_width, env_height) = if let Some(environment) = world.get_resource::<crate::environment::Environment>() {
-            (environment.get_width() as f32, environment.get_height() as f32)
-        } else {
-            // Fallback to some default dimensions if environment isn't available
-            (800.0, 600.0)
+        let at_colony: Vec<(EntityId, EntityId)> = match world.get_resource::<Contacts>() {
+            Some(contacts) => contacts.between(CollisionLayer::Ant, CollisionLayer::Colony).collect(),
+            None => Vec::new(),
         };
-
-        for entity_id in entity_ids {
-            // First, read the current state
-            let position_opt = world.get_component::<super::component::PositionComponent>(
-                entity_id,
-                super::component::ComponentType::Position,
-            );
-            
-            let ant_state_opt = world.get_component::<super::component::AntStateComponent>(
-                entity_id,
-                super::component::ComponentType::AntState,
-            );
-            
-            let velocity_opt = world.get_component::<super::component::VelocityComponent>(
-                entity_id,
-                super::component::ComponentType::Velocity,
-            );
-            
-            // Skip if any component is missing
-            if position_opt.is_none() || ant_state_opt.is_none() || velocity_opt.is_none() {
+        let resting: HashSet<EntityId> = at_colony.into_iter()
+            .filter(|&(ant_id, colony_id)| {
+                world.get_component::<AntStateComponent>(ant_id)
+                    .is_some_and(|state| state.colony_id == Some(colony_id))
+            })
+            .map(|(ant_id, _)| ant_id)
+            .collect();
+        
+        let mut dead = Vec::new();
+        for (entity_id, health) in world.query::<(EntityId, &mut HealthComponent)>() {
+            if resting.contains(&entity_id) {
+                health.restore();
                 continue;
             }
             
-            // Unwrap the options
-            let position = position_opt.unwrap();
-            let ant_state = ant_state_opt.unwrap();
-            let velocity = velocity_opt.unwrap();
-            
-            // Calculate updates to be applied
-            
-            // Update timers
-            let mut new_pheromone_timer = ant_state.pheromone_timer - delta_time;
-            let mut new_random_direction_timer = ant_state.random_direction_timer - delta_time;
-            
-            // New velocity values
-            let mut new_direction = velocity.direction;
-            let mut new_dx = velocity.dx;
-            let mut new_dy = velocity.dy;
-            
-            // New ant state
-            let mut new_state = ant_state.state.clone();
-            let mut new_carrying_food = ant_state.carrying_food;
-            
-            // Reset random direction timer if needed
-            if new_random_direction_timer <= 0.0 {
-                // Random direction change
-                new_direction += (rand::random::<f32>() - 0.5) * PI / 2.0;
-                new_dx = new_direction.cos();
-                new_dy = new_direction.sin();
-                
-                // Reset timer
-                new_random_direction_timer = 0.5 + rand::random::<f32>() * 1.5; // 0.5 to 2.0 seconds
+            health.current -= health.decay_rate * delta_time;
+            if health.is_dead() {
+                dead.push(entity_id);
             }
-            
-            // Handle ant state behavior
-            match ant_state.state {
-                AntState::Idle => {
-                    // Transition to searching for food after a short delay
-                    new_state = AntState::SearchingForFood;
+        }
+        
+        for entity_id in dead {
+            if world.has_component::<AntStateComponent>(entity_id) {
+                crate::ant::despawn_ant(world, entity_id);
+            } else {
+                world.remove_entity(entity_id);
+            }
+        }
+    }
+    
+    fn name(&self) -> &str {
+        "HealthSystem"
+    }
+    
+    fn stage(&self) -> Stage {
+        Stage::Cleanup
+    }
+    
+    fn before(&self) -> &[&str] {
+        // Deaths are logged on the tick they happen
+        &["EventLogSystem"]
+    }
+}
+
+/// System that lets entities with a pheromone component mark their position.
+/// Emitters use the shared channel, so every colony smells them.
+#[derive(Default)]
+pub struct PheromoneEmitterSystem;
+
+impl PheromoneEmitterSystem {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl System for PheromoneEmitterSystem {
+    fn update(&mut self, world: &mut World, delta_time: f32) {
+        let mut environment = match world.remove_resource::<Environment>() {
+            Some(environment) => environment,
+            None => return,
+        };
+        
+        for (position, emitter) in world.query::<(&PositionComponent, &mut PheromoneComponent)>() {
+            emitter.timer -= delta_time;
+            if emitter.timer <= 0.0 {
+                emitter.timer += emitter.interval;
+                environment.pheromone_system().add_pheromone(
+                    position.x,
+                    position.y,
+                    emitter.pheromone_type,
+                    None,
+                    emitter.strength,
+                );
+            }
+        }
+        
+        world.add_resource(environment);
+    }
+    
+    fn name(&self) -> &str {
+        "PheromoneEmitterSystem"
+    }
+    
+    fn stage(&self) -> Stage {
+        Stage::Pheromone
+    }
+    
+    fn after(&self) -> &[&str] {
+        // Fresh deposits don't evaporate before anyone could smell them
+        &["EnvironmentSystem"]
+    }
+}
+
+/// System that lets ants raise the alarm. Ants that are badly hurt, or that
+/// touch an ant from another colony, mark their position with alarm pheromone.
+#[derive(Default)]
+pub struct AlarmSystem;
+
+impl AlarmSystem {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl System for AlarmSystem {
+    fn update(&mut self, world: &mut World, _delta_time: f32) {
+        let ant_pairs: Vec<(EntityId, EntityId)> = match world.get_resource::<Contacts>() {
+            Some(contacts) => contacts.between(CollisionLayer::Ant, CollisionLayer::Ant).collect(),
+            None => Vec::new(),
+        };
+        
+        // Ants that ran into an intruder, and the intruders themselves
+        let mut threatened = HashSet::new();
+        for (a, b) in ant_pairs {
+            let colony_of = |id| world.get_component::<AntStateComponent>(id).and_then(|state| state.colony_id);
+            if let (Some(a_colony), Some(b_colony)) = (colony_of(a), colony_of(b)) {
+                if a_colony != b_colony {
+                    threatened.insert(a);
+                    threatened.insert(b);
                 }
-                
-                AntState::SearchingForFood => {
-                    // Here we would check for food nearby and transition to returning home if found
-                    // For now, we just wander
-                    
-                    // Drop pheromones periodically
-                    if new_pheromone_timer <= 0.0 {
-                        // In a real implementation, we would drop home pheromones here
-                        new_pheromone_timer = 0.5; // Reset timer
-                    }
+            }
+        }
+        
+        let colony_params = colony_params(world);
+        
+        let mut environment = match world.remove_resource::<Environment>() {
+            Some(environment) => environment,
+            None => return,
+        };
+        
+        for (entity_id, position, ant_state, health) in world.query::<(
+            EntityId,
+            &PositionComponent,
+            &AntStateComponent,
+            Option<&HealthComponent>,
+        )>() {
+            let params = params_for(&colony_params, ant_state.colony_id);
+            let hurt = health.is_some_and(|health| health.current < health.max * params.wounded_health);
+            if hurt || threatened.contains(&entity_id) {
+                environment.pheromone_system().mark_pheromone(
+                    position.x,
+                    position.y,
+                    PheromoneType::Alarm,
+                    ant_state.colony_id,
+                    params.alarm_strength,
+                );
+            }
+        }
+        
+        world.add_resource(environment);
+    }
+    
+    fn name(&self) -> &str {
+        "AlarmSystem"
+    }
+    
+    fn stage(&self) -> Stage {
+        Stage::Pheromone
+    }
+    
+    fn after(&self) -> &[&str] {
+        // Fresh alarms don't evaporate before anyone could smell them
+        &["EnvironmentSystem"]
+    }
+}
+
+/// System that writes simulation events to the log
+pub struct EventLogSystem {
+    subscription: Subscription<SimEvent>,
+}
+
+impl EventLogSystem {
+    pub fn new(subscription: Subscription<SimEvent>) -> Self {
+        Self { subscription }
+    }
+}
+
+impl System for EventLogSystem {
+    fn update(&mut self, world: &mut World, _delta_time: f32) {
+        for event in world.read_events(&self.subscription) {
+            match event {
+                SimEvent::FoodPickedUp { ant, position, .. } => {
+                    debug!("Ant #{} picked up food at ({:.1},{:.1})", ant, position.x, position.y);
                 }
-                
-                AntState::ReturningHome => {
-                    // Calculate direction to home
-                    let home_direction = self.direction_to(
-                        position.x, 
-                        position.y, 
-                        ant_state.home_position.0, 
-                        ant_state.home_position.1
-                    );
-                    
-                    // Gradually adjust ant direction to head home
-                    let angle_diff = (home_direction - velocity.direction + PI) % (2.0 * PI) - PI;
-                    new_direction += angle_diff * 0.1; // Gradually turn
-                    
-                    new_dx = new_direction.cos();
-                    new_dy = new_direction.sin();
-                    
-                    // Drop food pheromones
-                    if new_pheromone_timer <= 0.0 {
-                        // In a real implementation, we would drop food pheromones here
-                        new_pheromone_timer = 0.3; // Reset timer
-                    }
-                    
-                    // Check if we've reached home
-                    let dx = position.x - ant_state.home_position.0;
-                    let dy = position.y - ant_state.home_position.1;
-                    let dist_sq = dx * dx + dy * dy;
-                    
-                    if dist_sq < 25.0 { // Within 5 units of home
-                        new_carrying_food = false;
-                        new_state = AntState::SearchingForFood;
-                        
-                        // In a real implementation, we would add food to the colony here
-                    }
+                SimEvent::FoodDelivered { ant, colony, amount } => {
+                    debug!("Ant #{} delivered {} food to colony #{}", ant, amount, colony);
                 }
-                
-                AntState::FollowingPheromone => {
-                    // Follow pheromone gradient
-                    // This is a simplified placeholder - in a real implementation,
-                    // we would check pheromone levels in different directions
-                    
-                    // Randomly decide to stop following and start searching
-                    if rand::random::<f32>() < 0.02 {
-                        new_state = AntState::SearchingForFood;
-                    }
+                SimEvent::AntSpawned { ant, position, .. } => {
+                    debug!("Created new ant #{} at position ({:.1},{:.1})", ant, position.x, position.y);
                 }
-            }
-            
-            // Boundary checking - bounce off edges
-            let margin = 10.0;
-            if position.x < margin {
-                new_direction = PI - new_direction;
-                new_dx = new_direction.cos();
-                new_dy = new_direction.sin();
-            } else if position.x > env_width - margin {
-                new_direction = PI - new_direction;
-                new_dx = new_direction.cos();
-                new_dy = new_direction.sin();
-            }
-            
-            if position.y < margin {
-                new_direction = -new_direction;
-                new_dx = new_direction.cos();
-                new_dy = new_direction.sin();
-            } else if position.y > env_height - margin {
-                new_direction = -new_direction;
-                new_dx = new_direction.cos();
-                new_dy = new_direction.sin();
-            }
-            
-            // Check for wall collisions - this requires access to the environment
-            // In a full implementation, we would either:
-            // 1. Have an environment component that can be queried
-            // 2. Pass the environment as a parameter to the system
-            if let Some(environment) = world.get_resource::<crate::environment::Environment>() {
-                // Calculate next position
-                let next_x = position.x + new_dx * delta_time * 50.0; // Assuming speed is 50.0
-                let next_y = position.y + new_dy * delta_time * 50.0;
-                
-                // Check if next position would hit a wall
-                let (grid_x, grid_y) = environment.screen_to_grid(next_x, next_y);
-                if environment.get_cell(grid_x, grid_y) == crate::environment::CellType::Wall {
-                    // Determine wall orientation by checking adjacent cells
-                    let (current_grid_x, current_grid_y) = environment.screen_to_grid(position.x, position.y);
-                    
-                    let horizontal_wall = current_grid_x != grid_x && 
-                                         environment.get_cell(grid_x, current_grid_y) == crate::environment::CellType::Wall;
-                    
-                    let vertical_wall = current_grid_y != grid_y && 
-                                       environment.get_cell(current_grid_x, grid_y) == crate::environment::CellType::Wall;
-                    
-                    if horizontal_wall {
-                        // Bounce horizontally (like a side wall)
-                        new_direction = PI - new_direction;
-                    } else if vertical_wall {
-                        // Bounce vertically (like a floor/ceiling)
-                        new_direction = -new_direction;
-                    } else {
-                        // Corner collision or diagonal approach, reverse direction
-                        new_direction = (new_direction + PI) % (2.0 * PI);
-                    }
-                    
-                    // Add small random variation to prevent getting stuck
-                    new_direction += (rand::random::<f32>() - 0.5) * 0.2;
-                    
-                    // Update movement vector
-                    new_dx = new_direction.cos();
-                    new_dy = new_direction.sin();
+                SimEvent::AntDied { ant, position, .. } => {
+                    info!("Ant #{} died at ({:.1},{:.1})", ant, position.x, position.y);
                 }
-                
-                // Update boundary checking with actual environment dimensions
-                let env_width = environment.get_width() as f32;
-                let env_height = environment.get_height() as f32;
-                
-                if position.x < margin {
-                    new_direction = PI - new_direction;
-                    new_dx = new_direction.cos();
-                    new_dy = new_direction.sin();
-                } else if position.x > env_width - margin {
-                    new_direction = PI - new_direction;
-                    new_dx = new_direction.cos();
-                    new_dy = new_direction.sin();
+                SimEvent::FoodSourceDepleted { source, position } => {
+                    info!("Food source #{} at ({:.1},{:.1}) ran out", source, position.x, position.y);
                 }
-                
-                if position.y < margin {
-                    new_direction = -new_direction;
-                    new_dx = new_direction.cos();
-                    new_dy = new_direction.sin();
-                } else if position.y > env_height - margin {
-                    new_direction = -new_direction;
-                    new_dx = new_direction.cos();
-                    new_dy = new_direction.sin();
+                SimEvent::FoodCellDepleted { cell, .. } => {
+                    info!("Food cell ({},{}) ran out", cell.0, cell.1);
                 }
             }
-            
-            // Now apply all the state changes
-            if let Some(velocity) = world.get_component_mut::<super::component::VelocityComponent>(
-                entity_id,
-                super::component::ComponentType::Velocity,
-            ) {
-                velocity.direction = new_direction;
-                velocity.dx = new_dx;
-                velocity.dy = new_dy;
-            }
-            
-            if let Some(ant_state) = world.get_component_mut::<super::component::AntStateComponent>(
-                entity_id,
-                super::component::ComponentType::AntState,
-            ) {
-                ant_state.state = new_state;
-                ant_state.carrying_food = new_carrying_food;
-                ant_state.pheromone_timer = new_pheromone_timer;
-                ant_state.random_direction_timer = new_random_direction_timer;
-            }
         }
     }
     
     fn name(&self) -> &str {
-        "AntBehaviorSystem"
+        "EventLogSystem"
+    }
+    
+    fn stage(&self) -> Stage {
+        Stage::Cleanup
     }
 }
 
@@ -413,4 +769,4 @@ impl System for ExampleSystem {
     fn name(&self) -> &str {
         "ExampleSystem"
     }
-} 
\ No newline at end of file
+}
@@ -1,21 +1,83 @@
-use std::any::Any;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use super::component::{Component, ComponentType};
 use super::entity::{Entity, EntityId};
+use super::event::{EventChannel, Subscription};
+use super::query::{Query, QueryFilter, QueryIter, QueryRefIter, ReadOnlyQuery};
+use super::schedule::{Schedule, SystemInfo};
 use super::system::System;
 
+/// Storage for one component type.
+/// Slots of removed components are kept on a free list and handed out again,
+/// so the storage only grows to the largest number of live components.
+pub struct ComponentStorage {
+    pub(super) slots: Vec<Option<Box<dyn Component>>>,
+    free_slots: Vec<usize>,
+}
+
+impl ComponentStorage {
+    fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+        }
+    }
+    
+    /// Store a component and return the slot it went into
+    fn insert(&mut self, component: Box<dyn Component>) -> usize {
+        match self.free_slots.pop() {
+            Some(slot) => {
+                self.slots[slot] = Some(component);
+                slot
+            }
+            None => {
+                self.slots.push(Some(component));
+                self.slots.len() - 1
+            }
+        }
+    }
+    
+    /// Drop the component in a slot and make the slot available again
+    fn remove(&mut self, slot: usize) {
+        if let Some(component) = self.slots.get_mut(slot) {
+            if component.take().is_some() {
+                self.free_slots.push(slot);
+            }
+        }
+    }
+    
+    fn get(&self, slot: usize) -> Option<&dyn Component> {
+        self.slots.get(slot)?.as_deref()
+    }
+    
+    fn get_mut(&mut self, slot: usize) -> Option<&mut dyn Component> {
+        match self.slots.get_mut(slot)? {
+            Some(component) => Some(component.as_mut()),
+            None => None,
+        }
+    }
+}
+
+/// One entry in the world's entity table
+pub(super) struct EntitySlot {
+    // Generation of the entity living here, or of the next one if the slot is free
+    generation: u32,
+    pub(super) entity: Option<Entity>,
+}
+
 /// Main world struct that manages all entities, components and systems
 pub struct World {
-    // Entity management
-    entities: HashMap<EntityId, Entity>,
-    next_entity_id: EntityId,
+    // Entity management - entities are indexed by `EntityId::index`,
+    // free slots are reused with a bumped generation
+    entities: Vec<EntitySlot>,
+    free_entities: Vec<u32>,
     
-    // Component storage - each component type has a vector of components
-    components: HashMap<ComponentType, Vec<Box<dyn Component>>>,
+    // Component storage - each component type has its own slot storage,
+    // indexed by `ComponentType::index`
+    components: Vec<ComponentStorage>,
     
-    // Systems for updating the world
-    systems: Vec<Box<dyn System>>,
+    // Systems for updating the world and the order they run in
+    schedule: Schedule,
     
     // Resources - global data accessible to systems
     resources: HashMap<std::any::TypeId, Box<dyn std::any::Any>>,
@@ -25,10 +87,10 @@ impl World {
     /// Create a new empty world
     pub fn new() -> Self {
         Self {
-            entities: HashMap::new(),
-            next_entity_id: 0,
-            components: HashMap::new(),
-            systems: Vec::new(),
+            entities: Vec::new(),
+            free_entities: Vec::new(),
+            components: ComponentType::ALL.iter().map(|_| ComponentStorage::new()).collect(),
+            schedule: Schedule::new(),
             resources: HashMap::new(),
         }
     }
@@ -53,146 +115,245 @@ impl World {
             .and_then(|boxed| boxed.downcast_mut::<T>())
     }
     
+    /// Take a resource out of the world, e.g. to use it while also borrowing components.
+    /// Put it back with `add_resource` when done.
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        let type_id = std::any::TypeId::of::<T>();
+        self.resources.remove(&type_id)
+            .and_then(|boxed| boxed.downcast::<T>().ok())
+            .map(|boxed| *boxed)
+    }
+    
+    /// Publish an event on the world's `EventChannel<T>`
+    pub fn send_event<T: 'static>(&mut self, event: T) {
+        self.events_mut::<T>().send(event);
+    }
+    
+    /// Subscribe to the world's `EventChannel<T>`
+    pub fn subscribe<T: 'static>(&mut self) -> Subscription<T> {
+        self.events_mut::<T>().subscribe()
+    }
+    
+    /// Read the events of type `T` sent since the subscriber last read
+    pub fn read_events<T: 'static>(&mut self, subscription: &Subscription<T>) -> std::collections::vec_deque::Iter<'_, T> {
+        self.events_mut::<T>().read(subscription)
+    }
+    
+    /// Get the event channel for `T`, creating it the first time it is used
+    pub fn events_mut<T: 'static>(&mut self) -> &mut EventChannel<T> {
+        let type_id = std::any::TypeId::of::<EventChannel<T>>();
+        self.resources.entry(type_id)
+            .or_insert_with(|| Box::new(EventChannel::<T>::new()))
+            .downcast_mut::<EventChannel<T>>()
+            .expect("event channel resource has the wrong type")
+    }
+    
     /// Create a new entity and return its ID
     pub fn create_entity(&mut self) -> EntityId {
-        let entity_id = self.next_entity_id;
-        self.next_entity_id += 1;
+        let entity_id = match self.free_entities.pop() {
+            Some(index) => EntityId::new(index, self.entities[index as usize].generation),
+            None => {
+                self.entities.push(EntitySlot { generation: 0, entity: None });
+                EntityId::new((self.entities.len() - 1) as u32, 0)
+            }
+        };
         
-        let entity = Entity::new(entity_id);
-        self.entities.insert(entity_id, entity);
+        self.entities[entity_id.index() as usize].entity = Some(Entity::new(entity_id));
         
         entity_id
     }
     
+    /// Check whether an entity exists. Ids of removed entities are never alive again.
+    pub fn is_alive(&self, entity_id: EntityId) -> bool {
+        self.get_entity(entity_id).is_some()
+    }
+    
+    /// Number of live entities
+    pub fn entity_count(&self) -> usize {
+        self.entities.len() - self.free_entities.len()
+    }
+    
     /// Remove an entity and all its components
     pub fn remove_entity(&mut self, entity_id: EntityId) {
-        // Remove all components for this entity
-        if let Some(entity) = self.entities.get(&entity_id) {
-            for (component_type, &component_index) in &entity.components {
-                if let Some(components) = self.components.get_mut(component_type) {
-                    // Mark component as removed - we don't actually remove it
-                    // as that would invalidate indices for other entities
-                    components[component_index] = create_empty_component(*component_type);
-                }
+        if !self.is_alive(entity_id) {
+            return;
+        }
+        
+        let slot = &mut self.entities[entity_id.index() as usize];
+        if let Some(entity) = slot.entity.take() {
+            // Free the entity's component slots so new components can use them
+            for (component_type, component_index) in entity.components() {
+                self.components[component_type.index()].remove(component_index);
             }
         }
         
-        // Remove the entity
-        self.entities.remove(&entity_id);
+        // Bump the generation so old ids to this slot stop matching
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_entities.push(entity_id.index());
     }
     
-    /// Add a component to an entity
+    /// Add a component to an entity, replacing any component of the same type it already has
     pub fn add_component<T: Component>(&mut self, entity_id: EntityId, component: T) {
         let component_type = component.component_type();
         
-        // Get or create component storage for this type
-        let components = self.components
-            .entry(component_type)
-            .or_insert_with(Vec::new);
+        let entity = match self.entities.get_mut(entity_id.index() as usize) {
+            Some(EntitySlot { generation, entity: Some(entity) }) if *generation == entity_id.generation() => entity,
+            _ => return,
+        };
         
-        // Add component to storage and get its index
-        let component_index = components.len();
-        components.push(Box::new(component));
+        let storage = &mut self.components[component_type.index()];
         
-        // Add component reference to entity
-        if let Some(entity) = self.entities.get_mut(&entity_id) {
-            entity.add_component(component_type, component_index);
+        // Drop the component this one replaces
+        if let Some(old_index) = entity.get_component_index(&component_type) {
+            storage.remove(old_index);
         }
+        
+        // Add component to storage and remember its slot on the entity
+        let component_index = storage.insert(Box::new(component));
+        entity.add_component(component_type, component_index);
     }
     
     /// Remove a component from an entity
-    pub fn remove_component(&mut self, entity_id: EntityId, component_type: ComponentType) {
-        if let Some(entity) = self.entities.get_mut(&entity_id) {
+    pub fn remove_component<T: Component>(&mut self, entity_id: EntityId) {
+        let component_type = T::static_type();
+        let entity = match self.entities.get_mut(entity_id.index() as usize) {
+            Some(EntitySlot { generation, entity: Some(entity) }) if *generation == entity_id.generation() => entity,
+            _ => return,
+        };
+        
+        if let Some(component_index) = entity.get_component_index(&component_type) {
             entity.remove_component(&component_type);
+            self.components[component_type.index()].remove(component_index);
         }
     }
     
     /// Get a component for an entity by type
-    pub fn get_component<T: Component + 'static>(&self, entity_id: EntityId, component_type: ComponentType) -> Option<&T> {
-        let entity = self.entities.get(&entity_id)?;
-        let component_index = entity.get_component_index(&component_type)?;
-        let components = self.components.get(&component_type)?;
-        
-        if component_index >= components.len() {
-            return None;
-        }
+    pub fn get_component<T: Component>(&self, entity_id: EntityId) -> Option<&T> {
+        let component_type = T::static_type();
+        let component_index = self.get_entity(entity_id)?.get_component_index(&component_type)?;
         
-        components[component_index].as_any().downcast_ref::<T>()
+        self.components[component_type.index()].get(component_index)?.as_any().downcast_ref::<T>()
     }
     
     /// Get a mutable component for an entity by type
-    pub fn get_component_mut<T: Component + 'static>(&mut self, entity_id: EntityId, component_type: ComponentType) -> Option<&mut T> {
-        let entity = self.entities.get(&entity_id)?;
-        let component_index = entity.get_component_index(&component_type)?;
-        let components = self.components.get_mut(&component_type)?;
+    pub fn get_component_mut<T: Component>(&mut self, entity_id: EntityId) -> Option<&mut T> {
+        let component_type = T::static_type();
+        let component_index = self.get_entity(entity_id)?.get_component_index(&component_type)?;
         
-        if component_index >= components.len() {
-            return None;
-        }
-        
-        components[component_index].as_any_mut().downcast_mut::<T>()
+        self.components[component_type.index()].get_mut(component_index)?.as_any_mut().downcast_mut::<T>()
+    }
+    
+    /// Check whether an entity has a component of type `T`
+    pub fn has_component<T: Component>(&self, entity_id: EntityId) -> bool {
+        self.get_entity(entity_id)
+            .is_some_and(|entity| entity.has_component(&T::static_type()))
+    }
+    
+    /// Iterate over all entities that match a query, e.g.
+    /// `world.query::<(&mut PositionComponent, &VelocityComponent)>()`
+    pub fn query<Q: Query>(&mut self) -> QueryIter<'_, Q> {
+        self.query_filtered::<Q, ()>()
+    }
+    
+    /// Like `query`, but only visit entities that pass the filter, e.g.
+    /// `world.query_filtered::<&mut PositionComponent, Without<AntStateComponent>>()`
+    pub fn query_filtered<Q: Query, F: QueryFilter>(&mut self) -> QueryIter<'_, Q, F> {
+        // The world is borrowed mutably for as long as the iterator lives
+        unsafe { QueryIter::new(&self.entities, self.components.as_mut_ptr()) }
+    }
+    
+    /// Iterate over all entities that match a read-only query on a shared world
+    pub fn query_ref<Q: ReadOnlyQuery>(&self) -> QueryRefIter<'_, Q> {
+        self.query_ref_filtered::<Q, ()>()
+    }
+    
+    /// Like `query_ref`, but only visit entities that pass the filter
+    pub fn query_ref_filtered<Q: ReadOnlyQuery, F: QueryFilter>(&self) -> QueryRefIter<'_, Q, F> {
+        QueryRefIter::new(&self.entities, &self.components)
     }
     
     /// Get all entities that have all of the specified component types
     pub fn get_entities_with_components(&self, component_types: &[ComponentType]) -> Vec<EntityId> {
-        let mut result = Vec::new();
-        
-        for (&entity_id, entity) in &self.entities {
-            let has_all_components = component_types
-                .iter()
-                .all(|component_type| entity.has_component(component_type));
-                
-            if has_all_components {
-                result.push(entity_id);
-            }
+        // Entities are stored by index, so this comes out in a stable order and
+        // systems consume random numbers deterministically
+        self.entities.iter()
+            .filter_map(|slot| slot.entity.as_ref())
+            .filter(|entity| {
+                component_types
+                    .iter()
+                    .all(|component_type| entity.has_component(component_type))
+            })
+            .map(|entity| entity.id())
+            .collect()
+    }
+    
+    /// Look up a live entity, ignoring ids whose entity has been removed
+    fn get_entity(&self, entity_id: EntityId) -> Option<&Entity> {
+        let slot = self.entities.get(entity_id.index() as usize)?;
+        if slot.generation != entity_id.generation() {
+            return None;
         }
-        
-        result
+        slot.entity.as_ref()
     }
     
-    /// Add a system to the world
+    /// Add a system to the world. It runs in its `System::stage`, ordered by its constraints.
     pub fn add_system<S: System + 'static>(&mut self, system: S) {
-        self.systems.push(Box::new(system));
+        self.schedule.add_system(Box::new(system));
     }
     
-    /// Update all systems
+    /// Turn a system on or off by name. Returns false if there is no such system.
+    pub fn set_system_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        self.schedule.set_enabled(name, enabled)
+    }
+    
+    /// Whether the named system is enabled, or None if there is no such system
+    pub fn is_system_enabled(&self, name: &str) -> Option<bool> {
+        self.schedule.is_enabled(name)
+    }
+    
+    /// All systems in the order they run, with their timing stats
+    pub fn systems(&mut self) -> Vec<SystemInfo<'_>> {
+        self.schedule.systems()
+    }
+    
+    /// Update all enabled systems
     pub fn update(&mut self, delta_time: f32) {
         // Since we can't borrow self mutably multiple times,
-        // we'll temporarily replace the systems with an empty vector,
-        // update each system, then put them back
-        let mut systems = std::mem::replace(&mut self.systems, Vec::new());
-        
-        for mut system in systems.drain(..) {
-            system.update(self, delta_time);
-            self.systems.push(system);
-        }
+        // we'll temporarily take the schedule out, run it and put it back
+        let mut schedule = std::mem::take(&mut self.schedule);
+        schedule.run(self, delta_time);
+        self.schedule = schedule;
     }
 }
 
-/// Create an empty component of the specified type
-/// This is used when "removing" components to avoid invalidating indices
-fn create_empty_component(component_type: ComponentType) -> Box<dyn Component> {
-    match component_type {
-        ComponentType::Position => Box::new(super::component::PositionComponent { x: 0.0, y: 0.0 }),
-        ComponentType::Velocity => Box::new(super::component::VelocityComponent { 
-            dx: 0.0, dy: 0.0, speed: 0.0, direction: 0.0 
-        }),
-        ComponentType::Appearance => Box::new(super::component::AppearanceComponent {
-            shape_type: super::component::ShapeType::Circle,
-            radius: 0.0,
-            width: 0.0,
-            height: 0.0,
-            color: (0, 0, 0),
-        }),
-        ComponentType::AntState => Box::new(super::component::AntStateComponent {
-            state: super::component::AntState::Idle,
-            carrying_food: false,
-            pheromone_timer: 0.0,
-            random_direction_timer: 0.0,
-            home_position: (0.0, 0.0),
-            colony_id: None,
-        }),
-        // Add empty versions for other component types as needed
-        _ => panic!("Unimplemented component type for empty creation"),
-    }
-} 
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::component::PositionComponent;
+
+    #[test]
+    fn stale_ids_are_rejected_after_the_slot_is_reused() {
+        let mut world = World::new();
+        let old = world.create_entity();
+        world.add_component(old, PositionComponent { x: 1.0, y: 2.0 });
+        world.remove_entity(old);
+
+        let new = world.create_entity();
+        assert_eq!(new.index(), old.index());
+        assert_ne!(new.generation(), old.generation());
+        world.add_component(new, PositionComponent { x: 3.0, y: 4.0 });
+
+        assert!(!world.is_alive(old));
+        assert!(world.get_component::<PositionComponent>(old).is_none());
+        assert!(world.get_component_mut::<PositionComponent>(old).is_none());
+        assert!(!world.has_component::<PositionComponent>(old));
+
+        // Writes through the stale id must not reach the new entity
+        world.add_component(old, PositionComponent { x: 9.0, y: 9.0 });
+        world.remove_component::<PositionComponent>(old);
+        world.remove_entity(old);
+        assert!(world.is_alive(new));
+        assert_eq!(world.get_component::<PositionComponent>(new).unwrap().x, 3.0);
+        assert_eq!(world.query_ref::<EntityId>().collect::<Vec<_>>(), vec![new]);
+    }
+}
@@ -0,0 +1,296 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+// How much food the food cells on the grid hold, how fast ants carry it off
+// and how new food appears. Rates are per second of simulated time; regrowth,
+// spawning and blooms are off unless configured.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodConfig {
+    // Food a newly placed food cell holds
+    pub amount_per_cell: f32,
+    // Food an ant takes from a cell or food source each time it picks some up
+    pub amount_per_pickup: f32,
+    // Food every food cell grows back per second. Cells that ran out grow back too and
+    // become food cells again once they hold a load
+    pub regrowth_rate: f32,
+    // Cells stop growing back once they hold this much; None means `amount_per_cell`
+    pub regrowth_cap: Option<f32>,
+    // New food patches appearing per second on average, somewhere on empty ground
+    pub spawn_rate: f32,
+    // How many cells a new patch covers
+    pub patch_size: PatchSize,
+    // Length of a year in seconds; blooms come round once a year. 0 makes every bloom happen once
+    pub year_length: f32,
+    pub blooms: Vec<Bloom>,
+}
+
+impl FoodConfig {
+    // Check that the settings make sense: amounts must be positive, rates,
+    // lengths and multipliers must not be negative. Returns what is wrong
+    // with the first bad setting.
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [
+            ("amount_per_cell", self.amount_per_cell),
+            ("amount_per_pickup", self.amount_per_pickup),
+        ];
+        for (name, value) in positive {
+            if value.is_nan() || value <= 0.0 {
+                return Err(format!("{} must be positive, got {}", name, value));
+            }
+        }
+
+        let mut not_negative = vec![
+            ("regrowth_rate", self.regrowth_rate),
+            ("regrowth_cap", self.regrowth_cap()),
+            ("spawn_rate", self.spawn_rate),
+            ("year_length", self.year_length),
+        ];
+        if let PatchSize::Exponential { mean, .. } = self.patch_size {
+            not_negative.push(("patch_size mean", mean));
+        }
+        for bloom in &self.blooms {
+            not_negative.push(("bloom start", bloom.start));
+            not_negative.push(("bloom duration", bloom.duration));
+            not_negative.push(("bloom multiplier", bloom.multiplier));
+        }
+        for (name, value) in not_negative {
+            if value.is_nan() || value < 0.0 {
+                return Err(format!("{} must not be negative, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+
+    // The most food a cell grows back to
+    pub fn regrowth_cap(&self) -> f32 {
+        self.regrowth_cap.unwrap_or(self.amount_per_cell)
+    }
+
+    // How many times faster food grows back and spawns at `time`, from the blooms under way
+    pub fn bloom_multiplier(&self, time: f64) -> f32 {
+        self.blooms.iter()
+            .filter(|bloom| bloom.is_active(time, self.year_length))
+            .map(|bloom| bloom.multiplier)
+            .product()
+    }
+
+    // Food patches due from blooms starting at or after `from` and before `to`
+    pub fn bloom_patches(&self, from: f64, to: f64) -> u32 {
+        self.blooms.iter()
+            .filter(|bloom| bloom.starts_between(from, to, self.year_length))
+            .map(|bloom| bloom.patches)
+            .sum()
+    }
+}
+
+impl Default for FoodConfig {
+    fn default() -> Self {
+        Self {
+            amount_per_cell: 100.0,
+            amount_per_pickup: 1.0,
+            regrowth_rate: 0.0,
+            regrowth_cap: None,
+            spawn_rate: 0.0,
+            patch_size: PatchSize::default(),
+            year_length: 0.0,
+            blooms: Vec::new(),
+        }
+    }
+}
+
+// How many cells a newly spawned food patch covers
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum PatchSize {
+    // Always the same number of cells
+    Fixed { cells: u32 },
+    // Any number of cells from `min` to `max`, all equally likely
+    Uniform { min: u32, max: u32 },
+    // Mostly small patches with the odd large one, `mean` cells on average and never more than `max`
+    Exponential { mean: f32, max: u32 },
+}
+
+impl PatchSize {
+    // Draw a patch size, at least one cell
+    pub fn sample(&self, rng: &mut impl Rng) -> usize {
+        let cells = match *self {
+            PatchSize::Fixed { cells } => cells,
+            PatchSize::Uniform { min, max } => rng.gen_range(min.min(max)..=max.max(min)),
+            PatchSize::Exponential { mean, max } => {
+                let cells = (-mean * (1.0 - rng.gen::<f32>()).ln()).ceil();
+                (cells as u32).min(max)
+            }
+        };
+        cells.max(1) as usize
+    }
+}
+
+impl Default for PatchSize {
+    fn default() -> Self {
+        PatchSize::Uniform { min: 3, max: 12 }
+    }
+}
+
+// A season of plenty that comes round every year
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bloom {
+    // Seconds into the year the bloom starts
+    pub start: f32,
+    // How long it lasts in seconds
+    pub duration: f32,
+    // Food grows back and spawns this many times faster while it lasts
+    pub multiplier: f32,
+    // Food patches that appear as it starts
+    pub patches: u32,
+}
+
+impl Bloom {
+    // Whether the bloom is under way `time` seconds into the simulation. The
+    // clock is f64 so that long runs still land on the right tick
+    pub fn is_active(&self, time: f64, year_length: f32) -> bool {
+        let (start, year_length) = (self.start as f64, year_length as f64);
+        let since_start = if year_length > 0.0 {
+            (time - start).rem_euclid(year_length)
+        } else {
+            time - start
+        };
+        since_start >= 0.0 && since_start < self.duration as f64
+    }
+
+    // Whether the bloom starts at or after `from` and before `to`
+    pub fn starts_between(&self, from: f64, to: f64, year_length: f32) -> bool {
+        let (start, year_length) = (self.start as f64, year_length as f64);
+        if year_length > 0.0 {
+            ((to - start) / year_length).ceil() > ((from - start) / year_length).ceil()
+        } else {
+            from <= start && start < to
+        }
+    }
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            start: 0.0,
+            duration: 60.0,
+            multiplier: 1.0,
+            patches: 0,
+        }
+    }
+}
+
+// Read food settings from a JSON file, e.g. `{ "regrowth_rate": 0.5, "spawn_rate": 0.02 }`
+pub fn load_food_config(path: &Path) -> io::Result<FoodConfig> {
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    let config: FoodConfig = serde_json::from_str(&contents)?;
+    config.validate().map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn bloom(start: f32, duration: f32) -> Bloom {
+        Bloom { start, duration, ..Bloom::default() }
+    }
+
+    #[test]
+    fn bloom_is_active_from_its_start_up_to_its_end() {
+        let bloom = bloom(10.0, 5.0);
+        assert!(!bloom.is_active(9.999, 0.0));
+        assert!(bloom.is_active(10.0, 0.0));
+        assert!(bloom.is_active(14.999, 0.0));
+        assert!(!bloom.is_active(15.0, 0.0));
+        // Without a year it only happens once
+        assert!(!bloom.is_active(110.0, 0.0));
+    }
+
+    #[test]
+    fn bloom_comes_round_every_year() {
+        let bloom = bloom(10.0, 5.0);
+        assert!(bloom.is_active(110.0, 100.0));
+        assert!(!bloom.is_active(115.0, 100.0));
+        assert!(!bloom.is_active(109.999, 100.0));
+        // Still on the right tick after a million years
+        assert!(bloom.is_active(100_000_010.0, 100.0));
+        assert!(!bloom.is_active(100_000_009.99, 100.0));
+    }
+
+    #[test]
+    fn bloom_starts_once_in_each_window_that_covers_its_start() {
+        let bloom = bloom(10.0, 5.0);
+        assert!(!bloom.starts_between(9.9, 10.0, 0.0));
+        assert!(bloom.starts_between(10.0, 10.1, 0.0));
+        assert!(!bloom.starts_between(10.1, 20.0, 0.0));
+
+        // Counted in the window starting at 110 and not in the one ending there
+        assert!(!bloom.starts_between(109.9, 110.0, 100.0));
+        assert!(bloom.starts_between(110.0, 110.1, 100.0));
+        assert!(!bloom.starts_between(110.1, 200.0, 100.0));
+
+        // Ticks of 1/60 s far into a long run still see every start exactly once
+        let tick = 1.0 / 60.0;
+        let mut clock = 99_995.0;
+        let mut starts = 0;
+        while clock < 100_200.0 {
+            if bloom.starts_between(clock, clock + tick, 100.0) {
+                starts += 1;
+            }
+            clock += tick;
+        }
+        assert_eq!(starts, 2);
+    }
+
+    #[test]
+    fn patch_sizes_stay_in_range() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..1000 {
+            assert_eq!(PatchSize::Fixed { cells: 4 }.sample(&mut rng), 4);
+            assert!((3..=12).contains(&PatchSize::Uniform { min: 3, max: 12 }.sample(&mut rng)));
+            assert!((1..=20).contains(&PatchSize::Exponential { mean: 5.0, max: 20 }.sample(&mut rng)));
+        }
+        // Patches always cover at least one cell
+        assert_eq!(PatchSize::Fixed { cells: 0 }.sample(&mut rng), 1);
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(FoodConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn bad_food_settings_are_rejected() {
+        let config = FoodConfig { amount_per_pickup: 0.0, ..FoodConfig::default() };
+        assert!(config.validate().unwrap_err().contains("amount_per_pickup"));
+        let config = FoodConfig { regrowth_rate: -0.5, ..FoodConfig::default() };
+        assert!(config.validate().unwrap_err().contains("regrowth_rate"));
+        let config = FoodConfig { spawn_rate: f32::NAN, ..FoodConfig::default() };
+        assert!(config.validate().unwrap_err().contains("spawn_rate"));
+        let config = FoodConfig { year_length: -1.0, ..FoodConfig::default() };
+        assert!(config.validate().unwrap_err().contains("year_length"));
+        let config = FoodConfig {
+            blooms: vec![Bloom { multiplier: -2.0, ..Bloom::default() }],
+            ..FoodConfig::default()
+        };
+        assert!(config.validate().unwrap_err().contains("bloom multiplier"));
+    }
+
+    #[test]
+    fn regrowth_cap_defaults_to_amount_per_cell() {
+        let config = FoodConfig { amount_per_cell: 20.0, ..FoodConfig::default() };
+        assert_eq!(config.regrowth_cap(), 20.0);
+        let config = FoodConfig { regrowth_cap: Some(50.0), ..config };
+        assert_eq!(config.regrowth_cap(), 50.0);
+    }
+}
@@ -1,14 +1,26 @@
-use sfml::graphics::{RenderWindow, RenderTarget, RectangleShape, CircleShape, Color, Transformable, Shape};
-use sfml::system::Vector2f;
 use std::collections::HashMap;
+use log::{debug, info};
+use rand::Rng;
 use serde::{Serialize, Deserialize};
 
 use crate::pheromone::PheromoneSystem;
-use crate::colony::Colony;
-use crate::ant::Ant;
+use crate::math::Vector2f;
+use crate::rng::SimRng;
+
+pub mod food;
+pub use self::food::{load_food_config, Bloom, FoodConfig, PatchSize};
 
 // Cell size in pixels
-const CELL_SIZE: f32 = 10.0;
+pub const CELL_SIZE: f32 = 10.0;
+
+// Food cells holding less than this are used up
+const MIN_FOOD_AMOUNT: f32 = 0.001;
+
+// Random spots tried when looking for empty ground to spawn a food patch on
+const PATCH_SITE_ATTEMPTS: usize = 20;
+
+// Tries per cell to grow a food patch into a neighbouring empty cell
+const PATCH_GROWTH_ATTEMPTS: usize = 8;
 
 #[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum CellType {
@@ -25,12 +37,25 @@ pub struct Environment {
     grid_height: usize,
     grid: Vec<CellType>,
     food_amounts: HashMap<(usize, usize), f32>,
+    // Food cells that ran out, with what has grown back on them since
+    depleted_food: HashMap<(usize, usize), f32>,
+    food_config: FoodConfig,
+    food_clock: f64,
     pheromone_system: PheromoneSystem,
-    colonies: Vec<Colony>,
+    rng: SimRng,
 }
 
 impl Environment {
     pub fn new(window_width: u32, window_height: u32) -> Self {
+        Self::with_rng(window_width, window_height, SimRng::from_entropy())
+    }
+    
+    // Create an environment whose random decisions are reproducible from the seed
+    pub fn with_seed(window_width: u32, window_height: u32, seed: u64) -> Self {
+        Self::with_rng(window_width, window_height, SimRng::new(seed))
+    }
+    
+    fn with_rng(window_width: u32, window_height: u32, rng: SimRng) -> Self {
         let grid_width = (window_width as f32 / CELL_SIZE) as usize;
         let grid_height = (window_height as f32 / CELL_SIZE) as usize;
         
@@ -41,70 +66,119 @@ impl Environment {
             grid_height,
             grid: vec![CellType::Empty; grid_width * grid_height],
             food_amounts: HashMap::new(),
+            depleted_food: HashMap::new(),
+            food_config: FoodConfig::default(),
+            food_clock: 0.0,
             pheromone_system: PheromoneSystem::new(window_width, window_height, CELL_SIZE),
-            colonies: Vec::new(),
+            rng,
         }
     }
     
     pub fn update(&mut self, delta_time: f32) {
+        // Let pheromones spread and drift with the wind, walls block them
+        self.pheromone_system.diffuse(delta_time);
+        self.pheromone_system.advect(delta_time);
+        
         // Update pheromones
         self.pheromone_system.update(delta_time);
         
-        // Update colonies one at a time to avoid borrowing issues
-        let colony_count = self.colonies.len();
-        for i in 0..colony_count {
-            // Take out the colony temporarily to avoid borrowing the entire self
-            let mut colony = std::mem::take(&mut self.colonies[i]);
-            
-            // Update the colony
-            colony.update(delta_time, self);
+        self.update_food(delta_time);
+    }
+    
+    // Grow food back, spawn new patches and start blooms, following the food config
+    fn update_food(&mut self, delta_time: f32) {
+        let from = self.food_clock;
+        self.food_clock += delta_time as f64;
+        
+        let config = &self.food_config;
+        let multiplier = config.bloom_multiplier(self.food_clock);
+        
+        let mut regrown = Vec::new();
+        if config.regrowth_rate > 0.0 {
+            let growth = config.regrowth_rate * multiplier * delta_time;
+            let cap = config.regrowth_cap();
+            for amount in self.food_amounts.values_mut() {
+                if *amount < cap {
+                    *amount = (*amount + growth).min(cap);
+                }
+            }
             
-            // Put the colony back
-            self.colonies[i] = colony;
+            // Sites that ran out grow back too, and turn back into food cells
+            // once they hold a load again
+            let load = config.amount_per_pickup.min(cap);
+            for (&site, amount) in self.depleted_food.iter_mut() {
+                *amount = (*amount + growth).min(cap);
+                if *amount >= load {
+                    regrown.push((site, *amount));
+                }
+            }
+        }
+        
+        let mut patches = config.bloom_patches(from, self.food_clock);
+        // Only draw from the random number generator when spawning is on, so runs without it stay the same
+        if config.spawn_rate > 0.0 && self.rng.gen::<f32>() < config.spawn_rate * multiplier * delta_time {
+            patches += 1;
         }
         
-        // Update food regeneration, environmental effects, etc.
-        // For now, we'll keep this simple
+        for ((grid_x, grid_y), amount) in regrown {
+            self.set_cell(grid_x, grid_y, CellType::Food);
+            self.food_amounts.insert((grid_x, grid_y), amount);
+        }
+        
+        for _ in 0..patches {
+            self.spawn_food_patch();
+        }
     }
     
-    pub fn render(&self, window: &mut RenderWindow) {
-        // Render grid
-        for y in 0..self.grid_height {
-            for x in 0..self.grid_width {
-                let cell_type = self.get_cell(x, y);
-                
-                match cell_type {
-                    CellType::Empty => {},
-                    CellType::Wall => {
-                        let mut wall = RectangleShape::new();
-                        wall.set_size(Vector2f::new(CELL_SIZE, CELL_SIZE));
-                        wall.set_position(Vector2f::new(x as f32 * CELL_SIZE, y as f32 * CELL_SIZE));
-                        wall.set_fill_color(Color::rgb(100, 80, 60)); // Brown
-                        window.draw(&wall);
-                    },
-                    CellType::Food => {
-                        let mut food = CircleShape::new(CELL_SIZE / 2.0, 6);
-                        food.set_position(Vector2f::new(x as f32 * CELL_SIZE, y as f32 * CELL_SIZE));
-                        food.set_fill_color(Color::rgb(50, 200, 50)); // Green
-                        window.draw(&food);
-                    },
-                    CellType::AntNest => {
-                        let mut nest = CircleShape::new(CELL_SIZE * 2.0, 32);
-                        nest.set_position(Vector2f::new((x as f32 - 1.5) * CELL_SIZE, (y as f32 - 1.5) * CELL_SIZE));
-                        nest.set_fill_color(Color::rgb(150, 100, 50)); // Brown
-                        window.draw(&nest);
-                    },
-                }
+    // Spread a patch of food cells over empty ground at a random spot. The
+    // patch grows from one cell into random neighbours, so it comes out blobby.
+    // Returns how many cells it covers: fewer than drawn when it runs into walls,
+    // nests or the edge of the map, and none when no empty ground was found.
+    pub fn spawn_food_patch(&mut self) -> usize {
+        if self.grid_width == 0 || self.grid_height == 0 {
+            return 0;
+        }
+        
+        let size = self.food_config.patch_size.sample(&mut self.rng);
+        
+        let mut site = None;
+        for _ in 0..PATCH_SITE_ATTEMPTS {
+            let x = self.rng.gen_range(0..self.grid_width);
+            let y = self.rng.gen_range(0..self.grid_height);
+            if self.get_cell(x, y) == CellType::Empty {
+                site = Some((x, y));
+                break;
             }
         }
+        let Some((site_x, site_y)) = site else {
+            return 0;
+        };
         
-        // Render pheromones
-        self.pheromone_system.render(window);
+        self.place_food(site_x, site_y);
+        let mut cells = vec![(site_x, site_y)];
         
-        // Render colonies and ants
-        for colony in &self.colonies {
-            colony.render(window);
+        const NEIGHBOURS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+        for _ in 0..size * PATCH_GROWTH_ATTEMPTS {
+            if cells.len() >= size {
+                break;
+            }
+            
+            let (x, y) = cells[self.rng.gen_range(0..cells.len())];
+            let (dx, dy) = NEIGHBOURS[self.rng.gen_range(0..NEIGHBOURS.len())];
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            if nx < 0 || ny < 0 {
+                continue;
+            }
+            
+            let (nx, ny) = (nx as usize, ny as usize);
+            if self.is_valid_position(nx, ny) && self.get_cell(nx, ny) == CellType::Empty {
+                self.place_food(nx, ny);
+                cells.push((nx, ny));
+            }
         }
+        
+        info!("Food patch of {} cells spawned at ({},{})", cells.len(), site_x, site_y);
+        cells.len()
     }
     
     pub fn add_wall(&mut self, x: f32, y: f32) {
@@ -117,12 +191,50 @@ impl Environment {
     pub fn add_food(&mut self, x: f32, y: f32) {
         let (grid_x, grid_y) = self.screen_to_grid(x, y);
         if self.is_valid_position(grid_x, grid_y) {
-            self.set_cell(grid_x, grid_y, CellType::Food);
-            self.food_amounts.insert((grid_x, grid_y), 100.0); // Start with 100 units of food
+            self.place_food(grid_x, grid_y);
         }
     }
     
-    pub fn add_ant_nest(&mut self, x: f32, y: f32) {
+    // Turn the cell at grid coordinates into a full food cell
+    fn place_food(&mut self, grid_x: usize, grid_y: usize) {
+        self.set_cell(grid_x, grid_y, CellType::Food);
+        self.food_amounts.insert((grid_x, grid_y), self.food_config.amount_per_cell);
+    }
+    
+    // Take one ant's load of food from the food cell at grid coordinates.
+    // A cell that runs out turns back into empty ground, but keeps growing
+    // back while regrowth is on. Returns how much food was taken, 0 if the cell holds none.
+    pub fn take_food(&mut self, grid_x: usize, grid_y: usize) -> f32 {
+        if self.get_cell(grid_x, grid_y) != CellType::Food {
+            return 0.0;
+        }
+        
+        let amount_per_cell = self.food_config.amount_per_cell;
+        let remaining = self.food_amounts.entry((grid_x, grid_y)).or_insert(amount_per_cell);
+        let taken = remaining.min(self.food_config.amount_per_pickup);
+        *remaining -= taken;
+        
+        if *remaining < MIN_FOOD_AMOUNT {
+            self.food_amounts.remove(&(grid_x, grid_y));
+            self.set_cell(grid_x, grid_y, CellType::Empty);
+            self.depleted_food.insert((grid_x, grid_y), 0.0);
+        }
+        
+        taken
+    }
+    
+    // Food left in the cell at grid coordinates, 0 if it is not a food cell
+    pub fn get_food_amount(&self, grid_x: usize, grid_y: usize) -> f32 {
+        if self.get_cell(grid_x, grid_y) != CellType::Food {
+            return 0.0;
+        }
+        
+        // Food cells without a recorded amount are full
+        self.food_amounts.get(&(grid_x, grid_y)).copied().unwrap_or(self.food_config.amount_per_cell)
+    }
+    
+    // Mark out a 3x3 nest and return its center, where the colony should live
+    pub fn add_ant_nest(&mut self, x: f32, y: f32) -> Option<Vector2f> {
         let (grid_x, grid_y) = self.screen_to_grid(x, y);
         // Make sure we have enough space for the nest (it's larger than a single cell)
         if self.is_valid_position(grid_x, grid_y) {
@@ -142,14 +254,13 @@ impl Environment {
             let colony_x = (grid_x as f32 + 0.5) * CELL_SIZE;
             let colony_y = (grid_y as f32 + 0.5) * CELL_SIZE;
             
-            println!("DEBUG: Creating new colony at screen=({:.1},{:.1}), grid=({},{}), colony_pos=({:.1},{:.1})", 
+            debug!("Creating new colony at screen=({:.1},{:.1}), grid=({},{}), colony_pos=({:.1},{:.1})", 
                 x, y, grid_x, grid_y, colony_x, colony_y);
                 
-            // Create a new colony at this location with the correct radius matching the ant food delivery distance check
-            let colony_position = Vector2f::new(colony_x, colony_y);
-            let colony_radius = 30.0; // Match the distance check in the ant delivery code
-            self.colonies.push(Colony::new(colony_position, colony_radius));
+            return Some(Vector2f::new(colony_x, colony_y));
         }
+        
+        None
     }
     
     pub fn remove_object(&mut self, x: f32, y: f32) {
@@ -180,9 +291,20 @@ impl Environment {
     fn set_cell(&mut self, x: usize, y: usize, cell_type: CellType) {
         if self.is_valid_position(x, y) {
             self.grid[y * self.grid_width + x] = cell_type;
+            self.pheromone_system.set_barrier(x, y, cell_type == CellType::Wall);
+            // Whatever is put on a site that ran out replaces it for good
+            self.depleted_food.remove(&(x, y));
         }
     }
     
+    // Tell the pheromone system which cells are walls, after the grid was replaced
+    fn update_pheromone_barriers(&mut self) {
+        let (grid, grid_width, grid_height) = (&self.grid, self.grid_width, self.grid_height);
+        self.pheromone_system.set_barriers(|x, y| {
+            x < grid_width && y < grid_height && grid.get(y * grid_width + x) == Some(&CellType::Wall)
+        });
+    }
+    
     // Check if grid coordinates are valid
     fn is_valid_position(&self, x: usize, y: usize) -> bool {
         x < self.grid_width && y < self.grid_height
@@ -198,9 +320,24 @@ impl Environment {
         &self.pheromone_system
     }
     
-    // Get mutable reference to colonies
-    pub fn get_colonies(&mut self) -> &mut Vec<Colony> {
-        &mut self.colonies
+    // Get the simulation's random number generator
+    pub fn rng(&mut self) -> &mut SimRng {
+        &mut self.rng
+    }
+    
+    // Get the seed the random number generator was created with
+    pub fn get_seed(&self) -> u64 {
+        self.rng.seed()
+    }
+    
+    // Get how far the random number generator has got through its stream
+    pub fn get_rng_position(&self) -> u128 {
+        self.rng.position()
+    }
+    
+    // Continue the random number generator's stream from a saved position
+    pub fn set_rng_position(&mut self, position: u128) {
+        self.rng.set_position(position);
     }
     
     // Get the environment width
@@ -213,37 +350,33 @@ impl Environment {
         self.height
     }
     
-    // Resize the environment's grid to match the new window size
+    // Change the size of the world. Cells and pheromones inside both the old
+    // and the new size are kept; whatever falls outside a smaller world is dropped.
     pub fn resize(&mut self, new_width: u32, new_height: u32) {
         let new_grid_width = (new_width as f32 / CELL_SIZE) as usize;
         let new_grid_height = (new_height as f32 / CELL_SIZE) as usize;
         
-        // If the new size is larger, expand the grid
-        if new_grid_width > self.grid_width || new_grid_height > self.grid_height {
-            let mut new_grid = vec![CellType::Empty; new_grid_width * new_grid_height];
-            
-            // Copy existing grid data to the new grid
-            for y in 0..self.grid_height {
-                for x in 0..self.grid_width {
-                    if x < new_grid_width && y < new_grid_height {
-                        new_grid[y * new_grid_width + x] = self.grid[y * self.grid_width + x];
-                    }
-                }
+        let mut new_grid = vec![CellType::Empty; new_grid_width * new_grid_height];
+        
+        // Copy the part of the grid that still fits
+        for y in 0..self.grid_height.min(new_grid_height) {
+            for x in 0..self.grid_width.min(new_grid_width) {
+                new_grid[y * new_grid_width + x] = self.grid[y * self.grid_width + x];
             }
-            
-            self.grid = new_grid;
-            self.grid_width = new_grid_width;
-            self.grid_height = new_grid_height;
         }
         
+        self.grid = new_grid;
+        self.grid_width = new_grid_width;
+        self.grid_height = new_grid_height;
+        self.food_amounts.retain(|&(x, y), _| x < new_grid_width && y < new_grid_height);
+        self.depleted_food.retain(|&(x, y), _| x < new_grid_width && y < new_grid_height);
+        
         // Update internal width and height
         self.width = new_width;
         self.height = new_height;
         
-        // Resize the pheromone system
-        self.pheromone_system = PheromoneSystem::new(new_width, new_height, CELL_SIZE);
-        // Note: This will reset pheromones, but it's simpler than implementing
-        // a resize method for the pheromone system
+        self.pheromone_system.resize(new_width, new_height);
+        self.update_pheromone_barriers();
     }
     
     pub fn get_grid_width(&self) -> usize {
@@ -262,6 +395,7 @@ impl Environment {
         self.grid = grid;
         self.grid_width = width;
         self.grid_height = height;
+        self.update_pheromone_barriers();
     }
     
     pub fn get_food_amounts(&self) -> &HashMap<(usize, usize), f32> {
@@ -272,15 +406,143 @@ impl Environment {
         self.food_amounts = food_amounts;
     }
     
-    pub fn get_all_colonies(&self) -> &Vec<Colony> {
-        &self.colonies
+    // Food cells that ran out and what has grown back on them
+    pub fn get_depleted_food(&self) -> &HashMap<(usize, usize), f32> {
+        &self.depleted_food
+    }
+    
+    pub fn set_depleted_food(&mut self, depleted_food: HashMap<(usize, usize), f32>) {
+        self.depleted_food = depleted_food;
+    }
+    
+    pub fn get_food_config(&self) -> &FoodConfig {
+        &self.food_config
     }
     
-    pub fn clear_colonies(&mut self) {
-        self.colonies.clear();
+    // Change how much food cells hold and how much ants take. Cells already
+    // placed keep what they hold; only new cells get the new amount.
+    pub fn set_food_config(&mut self, food_config: FoodConfig) {
+        self.food_config = food_config;
     }
     
-    pub fn add_colony(&mut self, colony: Colony) {
-        self.colonies.push(colony);
+    // Seconds of simulated time the food rules have run, which sets where in the year it is
+    pub fn get_food_clock(&self) -> f64 {
+        self.food_clock
+    }
+    
+    pub fn set_food_clock(&mut self, food_clock: f64) {
+        self.food_clock = food_clock;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn food_cells(environment: &Environment) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for y in 0..environment.grid_height {
+            for x in 0..environment.grid_width {
+                if environment.get_cell(x, y) == CellType::Food {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn food_patch_grows_to_its_size_in_one_piece() {
+        let mut environment = Environment::with_seed(200, 200, 5);
+        environment.set_food_config(FoodConfig { patch_size: PatchSize::Fixed { cells: 6 }, ..FoodConfig::default() });
+
+        assert_eq!(environment.spawn_food_patch(), 6);
+        let cells = food_cells(&environment);
+        assert_eq!(cells.len(), 6);
+        for &(x, y) in &cells {
+            let touching = cells.iter().any(|&(ox, oy)| x.abs_diff(ox) + y.abs_diff(oy) == 1);
+            assert!(touching, "cell ({},{}) is cut off from the rest of the patch", x, y);
+        }
+    }
+
+    #[test]
+    fn food_patch_stops_at_the_edge_of_the_map() {
+        // A map two cells wide and one high
+        let mut environment = Environment::with_seed(20, 10, 5);
+        environment.set_food_config(FoodConfig { patch_size: PatchSize::Fixed { cells: 10 }, ..FoodConfig::default() });
+
+        assert_eq!(environment.spawn_food_patch(), 2);
+        assert_eq!(food_cells(&environment).len(), 2);
+    }
+
+    #[test]
+    fn food_grows_back_up_to_a_full_cell() {
+        let mut environment = Environment::with_seed(100, 100, 5);
+        environment.set_food_config(FoodConfig {
+            amount_per_cell: 10.0,
+            amount_per_pickup: 4.0,
+            regrowth_rate: 1.0,
+            ..FoodConfig::default()
+        });
+        environment.add_food(5.0, 5.0);
+        environment.take_food(0, 0);
+        assert_eq!(environment.get_food_amount(0, 0), 6.0);
+
+        environment.update_food(2.0);
+        assert_eq!(environment.get_food_amount(0, 0), 8.0);
+        environment.update_food(5.0);
+        assert_eq!(environment.get_food_amount(0, 0), 10.0);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn emptied_cells_grow_back() {
+        let mut environment = Environment::with_seed(100, 100, 5);
+        environment.set_food_config(FoodConfig {
+            amount_per_cell: 4.0,
+            amount_per_pickup: 4.0,
+            regrowth_rate: 1.0,
+            ..FoodConfig::default()
+        });
+        environment.add_food(5.0, 5.0);
+        assert_eq!(environment.take_food(0, 0), 4.0);
+        assert!(environment.get_cell(0, 0) == CellType::Empty);
+
+        // Not a full load yet
+        environment.update_food(3.0);
+        assert!(environment.get_cell(0, 0) == CellType::Empty);
+
+        environment.update_food(1.0);
+        assert!(environment.get_cell(0, 0) == CellType::Food);
+        assert_eq!(environment.get_food_amount(0, 0), 4.0);
+        assert_eq!(environment.take_food(0, 0), 4.0);
+    }
+
+    #[test]
+    fn emptied_cells_built_over_stay_gone() {
+        let mut environment = Environment::with_seed(100, 100, 5);
+        environment.set_food_config(FoodConfig { amount_per_cell: 1.0, regrowth_rate: 1.0, ..FoodConfig::default() });
+        environment.add_food(5.0, 5.0);
+        environment.take_food(0, 0);
+        environment.add_wall(5.0, 5.0);
+
+        environment.update_food(10.0);
+        assert!(environment.get_cell(0, 0) == CellType::Wall);
+    }
+
+    #[test]
+    fn blooms_spawn_their_patches_once_a_year() {
+        let mut environment = Environment::with_seed(200, 200, 5);
+        environment.set_food_config(FoodConfig {
+            patch_size: PatchSize::Fixed { cells: 1 },
+            year_length: 100.0,
+            blooms: vec![Bloom { start: 10.0, patches: 1, ..Bloom::default() }],
+            ..FoodConfig::default()
+        });
+
+        // Starts at 10, 110 and 210 seconds
+        for _ in 0..250 * 60 {
+            environment.update_food(1.0 / 60.0);
+        }
+        assert_eq!(food_cells(&environment).len(), 3);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::ecs::entity::EntityId;
+use crate::math::Vector2f;
+
+// Things that happen in the simulation that the rest of the program may want to
+// know about. Systems publish them on the world's `EventChannel<SimEvent>`;
+// the UI, statistics and logging subscribe instead of polling colonies.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimEvent {
+    // An ant picked up food at `position`
+    FoodPickedUp {
+        ant: EntityId,
+        colony: Option<EntityId>,
+        position: Vector2f,
+    },
+    // An ant brought food home and the colony stored it
+    FoodDelivered {
+        ant: EntityId,
+        colony: EntityId,
+        amount: f32,
+    },
+    // A new ant entity was created
+    AntSpawned {
+        ant: EntityId,
+        colony: Option<EntityId>,
+        position: Vector2f,
+    },
+    // An ant entity was removed from the world
+    AntDied {
+        ant: EntityId,
+        colony: Option<EntityId>,
+        position: Vector2f,
+    },
+    // A food source entity ran out of food and was removed
+    FoodSourceDepleted {
+        source: EntityId,
+        position: Vector2f,
+    },
+    // A food cell on the grid was emptied and turned back into empty ground
+    FoodCellDepleted {
+        cell: (usize, usize),
+        position: Vector2f,
+    },
+}
+
+// Running totals of simulation events, for statistics
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EventCounts {
+    pub food_picked_up: u64,
+    pub food_delivered: u64,
+    pub ants_spawned: u64,
+    pub ants_died: u64,
+    pub food_sources_depleted: u64,
+    pub food_cells_depleted: u64,
+}
+
+impl EventCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Count one event
+    pub fn record(&mut self, event: &SimEvent) {
+        match event {
+            SimEvent::FoodPickedUp { .. } => self.food_picked_up += 1,
+            SimEvent::FoodDelivered { .. } => self.food_delivered += 1,
+            SimEvent::AntSpawned { .. } => self.ants_spawned += 1,
+            SimEvent::AntDied { .. } => self.ants_died += 1,
+            SimEvent::FoodSourceDepleted { .. } => self.food_sources_depleted += 1,
+            SimEvent::FoodCellDepleted { .. } => self.food_cells_depleted += 1,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::environment::{CellType, Environment};
+use crate::pheromone::{PheromoneSystem, PheromoneType};
+
+// Snapshots of the pheromone field and the world grid, for plotting trail
+// formation in external tools. Images are binary grayscale PGM files with one
+// pixel per grid cell; the CSV holds the exact strengths.
+
+// Directory snapshots go to when no other is given
+pub const DEFAULT_EXPORT_DIR: &str = "export";
+
+// Write a snapshot of the environment into `dir`, creating it if needed.
+// Every file name ends with the tick it was taken at:
+// - `grid_<tick>.pgm`: the world grid (white empty, black walls, grey food and nests)
+// - `pheromone_<type>_<tick>.pgm`: one image per pheromone type, brighter where stronger
+// - `pheromones_<tick>.csv`: every cell holding pheromone, as `x,y,type,strength`
+// Pheromone that several colonies laid on the same cell is exported as the
+// strongest of them, which is what an ant sensing every colony's trails would smell.
+// Returns the paths of the files written.
+pub fn export_snapshot(environment: &Environment, dir: &Path, tick: u64) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+
+    let width = environment.get_grid_width();
+    let height = environment.get_grid_height();
+    let grid: Vec<u8> = environment.get_grid().iter().map(|&cell| cell_level(cell)).collect();
+    let path = dir.join(format!("grid_{}.pgm", tick));
+    write_pgm(&path, width, height, &grid)?;
+    written.push(path);
+
+    let pheromones = environment.pheromone_system_ref();
+    let (width, height) = pheromones.get_grid_dimensions();
+    let fields: Vec<(PheromoneType, Vec<f32>)> = PheromoneType::ALL.into_iter()
+        .map(|pheromone_type| (pheromone_type, pheromone_field(pheromones, pheromone_type)))
+        .collect();
+
+    for (pheromone_type, field) in &fields {
+        let max_strength = pheromones.get_config(*pheromone_type).max_strength;
+        let levels: Vec<u8> = field.iter()
+            .map(|strength| ((strength / max_strength).clamp(0.0, 1.0) * 255.0) as u8)
+            .collect();
+
+        let path = dir.join(format!("pheromone_{}_{}.pgm", type_name(*pheromone_type), tick));
+        write_pgm(&path, width, height, &levels)?;
+        written.push(path);
+    }
+
+    let path = dir.join(format!("pheromones_{}.csv", tick));
+    let mut csv = BufWriter::new(File::create(&path)?);
+    writeln!(csv, "x,y,type,strength")?;
+    for (pheromone_type, field) in &fields {
+        for (cell, strength) in field.iter().enumerate() {
+            if *strength > 0.0 {
+                writeln!(csv, "{},{},{},{}", cell % width, cell / width, type_name(*pheromone_type), strength)?;
+            }
+        }
+    }
+    csv.flush()?;
+    written.push(path);
+
+    Ok(written)
+}
+
+// Strongest pheromone of one type in each cell across all channels, row by row
+fn pheromone_field(pheromones: &PheromoneSystem, pheromone_type: PheromoneType) -> Vec<f32> {
+    let (width, height) = pheromones.get_grid_dimensions();
+    let mut field = vec![0.0_f32; width * height];
+
+    for ((grid_x, grid_y, cell_type), _, strength) in pheromones.get_all_pheromones() {
+        if cell_type == pheromone_type {
+            let cell = &mut field[grid_y * width + grid_x];
+            *cell = cell.max(strength);
+        }
+    }
+
+    field
+}
+
+// Write an 8-bit binary PGM image, row by row from the top
+fn write_pgm(path: &Path, width: usize, height: usize, pixels: &[u8]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "P5\n{} {}\n255\n", width, height)?;
+    file.write_all(pixels)?;
+    file.flush()
+}
+
+// Grey level of a cell type in the grid image
+fn cell_level(cell: CellType) -> u8 {
+    match cell {
+        CellType::Empty => 255,
+        CellType::Food => 170,
+        CellType::AntNest => 85,
+        CellType::Wall => 0,
+    }
+}
+
+// Lowercase name of a pheromone type, for file names and CSV rows
+fn type_name(pheromone_type: PheromoneType) -> &'static str {
+    match pheromone_type {
+        PheromoneType::Food => "food",
+        PheromoneType::Home => "home",
+        PheromoneType::Alarm => "alarm",
+    }
+}
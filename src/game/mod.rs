@@ -1,14 +1,22 @@
-use sfml::graphics::{RenderWindow, RenderTarget, Color};
+use sfml::graphics::{RenderWindow, RenderTarget, Color, FloatRect, View};
+use sfml::system::{Vector2f, Vector2i};
 use sfml::window::{Style, Event, Key, mouse};
-use sfml::system::Vector2f;
 use sfml::cpp::FBox;
 use std::path::Path;
+use std::time::Instant;
 
+use crate::ecs::event::Subscription;
 use crate::environment::Environment;
+use crate::events::SimEvent;
+use crate::export;
+use crate::simulation::Simulation;
 use crate::ui::UI;
-use crate::ant::Ant;
-use crate::save;
-use rand::random;
+use crate::save::{self, SavedAnt, SavedEnvironment, SavedGame};
+use crate::pheromone::PheromoneType;
+use crate::render::SimulationRenderer;
+use crate::timestep::FixedTimestep;
+use rand::Rng;
+use log::info;
 
 #[derive(Clone, Debug)]
 pub enum InteractionMode {
@@ -22,14 +30,21 @@ pub enum InteractionMode {
 
 pub struct Game {
     window: FBox<RenderWindow>,
-    environment: Environment,
+    // The world has a fixed size; this view fits it into the window, whatever the window's size
+    world_view: FBox<View>,
+    // Window pixels, for the UI
+    ui_view: FBox<View>,
+    simulation: Simulation,
+    renderer: SimulationRenderer,
+    sim_events: Subscription<SimEvent>,
     ui: UI,
     interaction_mode: InteractionMode,
     simulation_speed: f32,
     paused: bool,
     left_mouse_pressed: bool,
-    test_ants: Vec<Ant>, // Just for testing, will move to ECS later
     save_path: String,
+    timestep: FixedTimestep,
+    last_frame: Instant,
 }
 
 impl Game {
@@ -44,28 +59,41 @@ impl Game {
             Err(e) => panic!("Failed to create render window: {:?}", e),
         };
         
+        let window_rect = FloatRect::new(0.0, 0.0, width as f32, height as f32);
+        let (world_view, ui_view) = match (View::from_rect(window_rect), View::from_rect(window_rect)) {
+            (Ok(world_view), Ok(ui_view)) => (world_view, ui_view),
+            (Err(e), _) | (_, Err(e)) => panic!("Failed to create view: {:?}", e),
+        };
+        
         let environment = Environment::new(width, height);
+        info!("Simulation seed: {}", environment.get_seed());
+        let mut simulation = Simulation::new(environment);
+        let sim_events = simulation.subscribe();
         let ui = UI::new(width, height);
         
-        // Create some test ants
-        let mut test_ants = Vec::new();
+        // Create some test ants that don't belong to any colony
         for _ in 0..50 {
-            test_ants.push(Ant::new(
-                random::<f32>() * width as f32,
-                random::<f32>() * height as f32
-            ));
+            let rng = simulation.environment().rng();
+            let x = rng.gen::<f32>() * width as f32;
+            let y = rng.gen::<f32>() * height as f32;
+            simulation.add_ant(x, y, None);
         }
         
         Self {
             window,
-            environment,
+            world_view,
+            ui_view,
+            simulation,
+            renderer: SimulationRenderer::new(),
+            sim_events,
             ui,
             interaction_mode: InteractionMode::None,
             simulation_speed: 1.0,
             paused: false,
             left_mouse_pressed: false,
-            test_ants,
             save_path: "ant_simulation_save.json".to_string(),
+            timestep: FixedTimestep::default(),
+            last_frame: Instant::now(),
         }
     }
     
@@ -99,21 +127,12 @@ impl Game {
                     self.handle_mouse_move(x, y);
                 }
                 Event::Resized { width, height } => {
-                    // Get the current view
-                    let mut view = self.window.view().to_owned();
-                    
-                    // Update the view size
-                    view.set_size((width as f32, height as f32));
-                    view.set_center((width as f32 / 2.0, height as f32 / 2.0));
-                    
-                    // Apply the view
-                    self.window.set_view(&view);
+                    // Only the view changes; the world keeps its size and contents
+                    self.ui_view.reset(FloatRect::new(0.0, 0.0, width as f32, height as f32));
+                    self.fit_world_view();
                     
                     // Inform UI of the resize
                     self.ui.resize(width, height);
-                    
-                    // Update environment to handle the new window size
-                    self.environment.resize(width, height);
                 }
                 _ => {}
             }
@@ -134,6 +153,23 @@ impl Game {
             Key::N => self.interaction_mode = InteractionMode::AddAntNest,
             Key::A => self.interaction_mode = InteractionMode::AddAnt,
             Key::Escape => self.window.close(),
+            // F1-F9 switch the simulation's systems on and off, in the order they run
+            Key::F1 => self.toggle_system(0),
+            Key::F2 => self.toggle_system(1),
+            Key::F3 => self.toggle_system(2),
+            Key::F4 => self.toggle_system(3),
+            Key::F5 => self.toggle_system(4),
+            Key::F6 => self.toggle_system(5),
+            Key::F7 => self.toggle_system(6),
+            Key::F8 => self.toggle_system(7),
+            Key::F9 => self.toggle_system(8),
+            Key::F12 => self.print_system_stats(),
+            Key::C => self.toggle_cross_colony_pheromones(),
+            Key::M => self.cycle_colormap(),
+            Key::Num1 => self.toggle_pheromone_visibility(PheromoneType::Food),
+            Key::Num2 => self.toggle_pheromone_visibility(PheromoneType::Home),
+            Key::Num3 => self.toggle_pheromone_visibility(PheromoneType::Alarm),
+            Key::E => self.export_snapshot(),
             Key::S => {
                 // Save the game state
                 if let Err(e) = self.save_game() {
@@ -154,22 +190,110 @@ impl Game {
         }
     }
     
+    fn toggle_system(&mut self, index: usize) {
+        let world = self.simulation.world();
+        let system = world.systems().get(index).map(|system| (system.name.to_string(), system.enabled));
+        
+        if let Some((name, enabled)) = system {
+            world.set_system_enabled(&name, !enabled);
+            println!("{} {}", name, if enabled { "disabled" } else { "enabled" });
+        }
+    }
+    
+    fn toggle_cross_colony_pheromones(&mut self) {
+        let pheromones = self.simulation.environment().pheromone_system();
+        let cross_colony = !pheromones.get_cross_colony_sensing();
+        pheromones.set_cross_colony_sensing(cross_colony);
+        println!("Cross-colony pheromone sensing {}", if cross_colony { "enabled" } else { "disabled" });
+    }
+    
+    fn cycle_colormap(&mut self) {
+        let heatmap = self.renderer.heatmap();
+        let colormap = heatmap.get_colormap().next();
+        heatmap.set_colormap(colormap);
+        println!("Pheromone colormap: {:?}", colormap);
+    }
+    
+    fn toggle_pheromone_visibility(&mut self, pheromone_type: PheromoneType) {
+        let heatmap = self.renderer.heatmap();
+        let visible = !heatmap.is_visible(pheromone_type);
+        heatmap.set_visible(pheromone_type, visible);
+        println!("{:?} pheromones {}", pheromone_type, if visible { "shown" } else { "hidden" });
+    }
+    
+    fn export_snapshot(&self) {
+        let dir = Path::new(export::DEFAULT_EXPORT_DIR);
+        let tick = self.get_tick();
+        match export::export_snapshot(self.simulation.environment_ref(), dir, tick) {
+            Ok(written) => println!("Exported {} files for tick {} to {}", written.len(), tick, dir.display()),
+            Err(e) => println!("Error exporting data: {}", e),
+        }
+    }
+    
+    fn print_system_stats(&mut self) {
+        for system in self.simulation.world().systems() {
+            println!(
+                "{:?} {}: avg={:.3}ms max={:.3}ms last={:.3}ms{}",
+                system.stage,
+                system.name,
+                system.stats.average().as_secs_f64() * 1000.0,
+                system.stats.max.as_secs_f64() * 1000.0,
+                system.stats.last.as_secs_f64() * 1000.0,
+                if system.enabled { "" } else { " (disabled)" }
+            );
+        }
+    }
+    
+    // Show the whole world as large as the window allows, keeping its
+    // proportions, with bars on the sides the window has too much of
+    fn fit_world_view(&mut self) {
+        let environment = self.simulation.environment_ref();
+        let (world_width, world_height) = (environment.get_width() as f32, environment.get_height() as f32);
+        let window_size = self.window.size();
+        let (window_width, window_height) = (window_size.x as f32, window_size.y as f32);
+        
+        let scale = (window_width / world_width).min(window_height / world_height);
+        let (viewport_width, viewport_height) = (world_width * scale / window_width, world_height * scale / window_height);
+        
+        self.world_view.reset(FloatRect::new(0.0, 0.0, world_width, world_height));
+        self.world_view.set_viewport(FloatRect::new(
+            (1.0 - viewport_width) / 2.0,
+            (1.0 - viewport_height) / 2.0,
+            viewport_width,
+            viewport_height,
+        ));
+    }
+    
+    // The world position under a window pixel, or None if the pixel is outside the world
+    fn world_position(&self, x: i32, y: i32) -> Option<Vector2f> {
+        let position = self.window.map_pixel_to_coords(Vector2i::new(x, y), &self.world_view);
+        let environment = self.simulation.environment_ref();
+        
+        let inside = (0.0..environment.get_width() as f32).contains(&position.x)
+            && (0.0..environment.get_height() as f32).contains(&position.y);
+        inside.then_some(position)
+    }
+    
     fn handle_mouse_press(&mut self, x: i32, y: i32) {
+        let Some(Vector2f { x, y }) = self.world_position(x, y) else {
+            return;
+        };
+        
         match self.interaction_mode {
             InteractionMode::AddWall => {
-                self.environment.add_wall(x as f32, y as f32);
+                self.simulation.environment().add_wall(x, y);
             }
             InteractionMode::AddFood => {
-                self.environment.add_food(x as f32, y as f32);
+                self.simulation.environment().add_food(x, y);
             }
             InteractionMode::RemoveObject => {
-                self.environment.remove_object(x as f32, y as f32);
+                self.simulation.environment().remove_object(x, y);
             }
             InteractionMode::AddAntNest => {
-                self.environment.add_ant_nest(x as f32, y as f32);
+                self.simulation.add_ant_nest(x, y);
             }
             InteractionMode::AddAnt => {
-                self.test_ants.push(Ant::new(x as f32, y as f32));
+                self.simulation.add_ant(x, y, None);
             }
             _ => {}
         }
@@ -178,15 +302,19 @@ impl Game {
     fn handle_mouse_move(&mut self, x: i32, y: i32) {
         // Handle drag interactions
         if self.left_mouse_pressed {
+            let Some(Vector2f { x, y }) = self.world_position(x, y) else {
+                return;
+            };
+            
             match self.interaction_mode {
                 InteractionMode::AddWall => {
-                    self.environment.add_wall(x as f32, y as f32);
+                    self.simulation.environment().add_wall(x, y);
                 }
                 InteractionMode::AddFood => {
-                    self.environment.add_food(x as f32, y as f32);
+                    self.simulation.environment().add_food(x, y);
                 }
                 InteractionMode::RemoveObject => {
-                    self.environment.remove_object(x as f32, y as f32);
+                    self.simulation.environment().remove_object(x, y);
                 }
                 _ => {}
             }
@@ -194,31 +322,44 @@ impl Game {
     }
     
     fn update(&mut self) {
-        if !self.paused {
-            let delta_time = self.simulation_speed / 60.0; // Assuming 60 FPS
-            
-            // Update environment
-            self.environment.update(delta_time);
+        // Measure real time since the last frame, ignoring huge gaps
+        // (e.g. the window being dragged) so we don't try to catch up on them
+        let now = Instant::now();
+        let frame_time = now.duration_since(self.last_frame).as_secs_f32().min(0.25);
+        self.last_frame = now;
+        
+        if self.paused {
+            self.timestep.reset_accumulator();
+        } else {
+            // Simulation speed scales how much simulated time passes per real second,
+            // the size of each tick stays the same
+            let steps = self.timestep.advance(frame_time * self.simulation_speed);
+            let delta_time = self.timestep.step();
             
-            // Update test ants
-            for ant in &mut self.test_ants {
-                ant.update(delta_time, &mut self.environment);
+            for _ in 0..steps {
+                self.step(delta_time);
             }
         }
         
-        self.ui.update(&self.interaction_mode, self.simulation_speed, self.paused, &self.environment);
+        for event in self.simulation.read_events(&self.sim_events) {
+            self.ui.handle_sim_event(event);
+        }
+        
+        self.ui.update(&self.interaction_mode, self.simulation_speed, self.paused, self.timestep.tick());
+    }
+    
+    // Advance the simulation by one fixed tick
+    fn step(&mut self, delta_time: f32) {
+        self.simulation.update(delta_time);
     }
     
     fn render(&mut self) {
         self.window.clear(Color::rgb(240, 230, 210)); // Light sandy color
         
-        self.environment.render(&mut self.window);
-        
-        // Render test ants
-        for ant in &self.test_ants {
-            ant.render(&mut self.window);
-        }
+        self.window.set_view(&self.world_view);
+        self.renderer.render(&self.simulation, &mut self.window);
         
+        self.window.set_view(&self.ui_view);
         self.ui.render(&mut self.window);
         
         self.window.display();
@@ -226,21 +367,33 @@ impl Game {
     
     // Save the current game state
     pub fn save_game(&self) -> std::io::Result<()> {
-        save::save_game_state(Path::new(&self.save_path), self)
+        let game_state = SavedGame {
+            environment: SavedEnvironment::from_simulation(&self.simulation),
+            interaction_mode: format!("{:?}", self.interaction_mode),
+            simulation_speed: self.simulation_speed,
+            paused: self.paused,
+            test_ants: SavedAnt::free_ants(&self.simulation),
+            tick: self.timestep.tick(),
+        };
+        save::save_game_state(Path::new(&self.save_path), &game_state)
     }
     
     // Load a game state
     pub fn load_game(&mut self) -> std::io::Result<()> {
         let saved_game = save::load_game_state(Path::new(&self.save_path))?;
         
-        // Load environment
-        self.environment = saved_game.environment.to_environment();
+        // Load the environment, colonies and ants
+        self.simulation = saved_game.to_simulation();
+        self.sim_events = self.simulation.subscribe();
         
-        // Load test ants
-        self.test_ants.clear();
-        for saved_ant in saved_game.test_ants {
-            self.test_ants.push(saved_ant.to_ant());
-        }
+        // The saved world may be a different size than the one it replaces
+        self.fit_world_view();
+        
+        // Events only cover what happens from now on, so start from the saved totals
+        let food_deliveries = self.simulation.get_colonies().iter()
+            .map(|(_, colony)| colony.get_food_deliveries())
+            .sum();
+        self.ui.set_food_deliveries(food_deliveries);
         
         // Parse interaction mode from string
         match saved_game.interaction_mode.as_str() {
@@ -256,13 +409,15 @@ impl Game {
         // Load other game settings
         self.simulation_speed = saved_game.simulation_speed;
         self.paused = saved_game.paused;
+        self.timestep.set_tick(saved_game.tick);
+        self.timestep.reset_accumulator();
         
         Ok(())
     }
     
     // Accessor methods for save/load functionality
-    pub fn get_environment(&self) -> &Environment {
-        &self.environment
+    pub fn get_simulation(&self) -> &Simulation {
+        &self.simulation
     }
     
     pub fn get_interaction_mode(&self) -> &InteractionMode {
@@ -277,7 +432,8 @@ impl Game {
         self.paused
     }
     
-    pub fn get_test_ants(&self) -> &Vec<Ant> {
-        &self.test_ants
+    // Number of fixed simulation ticks run so far
+    pub fn get_tick(&self) -> u64 {
+        self.timestep.tick()
     }
 } 
\ No newline at end of file
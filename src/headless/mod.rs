@@ -0,0 +1,316 @@
+use log::info;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::ant::{self, Steering};
+use crate::environment::{self, CellType, Environment, FoodConfig};
+use crate::events::EventCounts;
+use crate::export;
+use crate::pheromone::{self, Wind};
+use crate::save;
+use crate::scenario;
+use crate::simulation::Simulation;
+use crate::timestep::FIXED_TIMESTEP;
+
+// Runs the simulation without opening a window, for batch experiments
+// on machines that have no display.
+pub struct HeadlessConfig {
+    pub ticks: u64,
+    pub delta_time: f32,
+    pub width: u32,
+    pub height: u32,
+    pub load_path: Option<PathBuf>,
+    pub scenario_path: Option<PathBuf>,
+    pub report_every: Option<u64>,
+    pub seed: Option<u64>,
+    pub diffusion: Option<f32>,
+    pub pheromone_config_path: Option<PathBuf>,
+    pub cross_colony_pheromones: bool,
+    pub wind: Option<Wind>,
+    pub food_config_path: Option<PathBuf>,
+    pub food_per_pickup: Option<f32>,
+    pub steering: Option<Steering>,
+    pub ant_params_path: Option<PathBuf>,
+    pub log_level: log::LevelFilter,
+    pub disabled_systems: Vec<String>,
+    pub system_stats: bool,
+    pub export_ticks: Vec<u64>,
+    pub export_dir: PathBuf,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self {
+            ticks: 3600, // One minute of simulated time at 60 ticks per second
+            delta_time: FIXED_TIMESTEP,
+            width: 1200,
+            height: 800,
+            load_path: None,
+            scenario_path: None,
+            report_every: None,
+            seed: None,
+            diffusion: None,
+            pheromone_config_path: None,
+            cross_colony_pheromones: false,
+            wind: None,
+            food_config_path: None,
+            food_per_pickup: None,
+            steering: None,
+            ant_params_path: None,
+            log_level: log::LevelFilter::Warn,
+            disabled_systems: Vec::new(),
+            system_stats: false,
+            export_ticks: Vec::new(),
+            export_dir: PathBuf::from(export::DEFAULT_EXPORT_DIR),
+        }
+    }
+}
+
+impl HeadlessConfig {
+    // Check whether the command line asks for a headless run
+    pub fn is_requested(args: &[String]) -> bool {
+        args.iter().any(|arg| arg == "--headless")
+    }
+
+    // Parse the headless options from the command line (program name excluded)
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--headless" => {}
+                "--ticks" => config.ticks = parse_value(arg, iter.next())?,
+                "--dt" => config.delta_time = parse_value(arg, iter.next())?,
+                "--width" => config.width = parse_value(arg, iter.next())?,
+                "--height" => config.height = parse_value(arg, iter.next())?,
+                "--report-every" => config.report_every = Some(parse_value(arg, iter.next())?),
+                "--load" => config.load_path = Some(PathBuf::from(expect_value(arg, iter.next())?)),
+                "--scenario" => config.scenario_path = Some(PathBuf::from(expect_value(arg, iter.next())?)),
+                "--seed" => config.seed = Some(parse_value(arg, iter.next())?),
+                "--diffusion" => config.diffusion = Some(parse_value(arg, iter.next())?),
+                "--cross-colony-pheromones" => config.cross_colony_pheromones = true,
+                "--wind" => config.wind = Some(parse_wind(arg, iter.next())?),
+                "--food-config" => config.food_config_path = Some(PathBuf::from(expect_value(arg, iter.next())?)),
+                "--food-per-pickup" => config.food_per_pickup = Some(parse_value(arg, iter.next())?),
+                "--steering" => config.steering = Some(parse_value(arg, iter.next())?),
+                "--ant-params" => config.ant_params_path = Some(PathBuf::from(expect_value(arg, iter.next())?)),
+                "--pheromone-config" => config.pheromone_config_path = Some(PathBuf::from(expect_value(arg, iter.next())?)),
+                "--log-level" => config.log_level = parse_value(arg, iter.next())?,
+                "--disable-system" => config.disabled_systems.push(expect_value(arg, iter.next())?.clone()),
+                "--system-stats" => config.system_stats = true,
+                "--export-at" => config.export_ticks.push(parse_value(arg, iter.next())?),
+                "--export-dir" => config.export_dir = PathBuf::from(expect_value(arg, iter.next())?),
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        if config.load_path.is_some() && config.scenario_path.is_some() {
+            return Err("--load and --scenario cannot be used together".to_string());
+        }
+
+        Ok(config)
+    }
+}
+
+fn expect_value<'a>(flag: &str, value: Option<&'a String>) -> Result<&'a String, String> {
+    value.ok_or_else(|| format!("Missing value for {}", flag))
+}
+
+// Parse a uniform wind given as `X,Y` in pixels per second
+fn parse_wind(flag: &str, value: Option<&String>) -> Result<Wind, String> {
+    let value = expect_value(flag, value)?;
+    let invalid = || format!("Invalid value for {}: {} (expected X,Y)", flag, value);
+    let (x, y) = value.split_once(',').ok_or_else(invalid)?;
+
+    Ok(Wind::Uniform {
+        x: x.trim().parse().map_err(|_| invalid())?,
+        y: y.trim().parse().map_err(|_| invalid())?,
+    })
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = expect_value(flag, value)?;
+    value.parse::<T>()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+pub fn run(config: &HeadlessConfig) -> io::Result<()> {
+    let mut simulation = build_simulation(config)?;
+
+    if let Some(rate) = config.diffusion {
+        simulation.environment().pheromone_system().set_diffusion_rate(rate);
+    }
+
+    if config.cross_colony_pheromones {
+        simulation.environment().pheromone_system().set_cross_colony_sensing(true);
+    }
+
+    let ant_params = match &config.ant_params_path {
+        Some(path) => Some(ant::load_ant_params(path)?),
+        None => None,
+    };
+
+    let colony_ids: Vec<_> = simulation.get_colonies().into_iter().map(|(colony_id, _)| colony_id).collect();
+    for colony_id in colony_ids {
+        let Some(colony) = simulation.get_colony_mut(colony_id) else {
+            continue;
+        };
+        if let Some(steering) = config.steering {
+            colony.set_steering(steering);
+        }
+        if let Some(params) = &ant_params {
+            colony.set_params(params.clone());
+        }
+    }
+
+    if let Some(wind) = &config.wind {
+        simulation.environment().pheromone_system().set_wind(wind.clone());
+    }
+
+    if let Some(path) = &config.food_config_path {
+        simulation.environment().set_food_config(environment::load_food_config(path)?);
+    }
+
+    if let Some(amount) = config.food_per_pickup {
+        let food_config = FoodConfig { amount_per_pickup: amount, ..simulation.environment_ref().get_food_config().clone() };
+        food_config.validate().map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, message))?;
+        simulation.environment().set_food_config(food_config);
+    }
+
+    if let Some(path) = &config.pheromone_config_path {
+        for (pheromone_type, pheromone_config) in pheromone::load_pheromone_configs(path)? {
+            simulation.environment().pheromone_system().set_config(pheromone_type, pheromone_config);
+        }
+    }
+
+    for name in &config.disabled_systems {
+        if !simulation.world().set_system_enabled(name, false) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown system: {}", name)));
+        }
+    }
+
+    // Tally what happens during the run from the simulation's events
+    let subscription = simulation.subscribe();
+    let mut event_counts = EventCounts::new();
+
+    let environment = simulation.environment_ref();
+
+    info!(
+        "Running headless simulation for {} ticks (dt={:.4}) on a {}x{} world with seed {}",
+        config.ticks, config.delta_time, environment.get_width(), environment.get_height(),
+        environment.get_seed()
+    );
+
+    for tick in 1..=config.ticks {
+        simulation.update(config.delta_time);
+        for event in simulation.read_events(&subscription) {
+            event_counts.record(event);
+        }
+
+        if config.export_ticks.contains(&tick) {
+            let written = export::export_snapshot(simulation.environment_ref(), &config.export_dir, tick)?;
+            info!("Exported {} files for tick {} to {}", written.len(), tick, config.export_dir.display());
+        }
+
+        if let Some(interval) = config.report_every {
+            if interval > 0 && tick % interval == 0 && tick != config.ticks {
+                print_statistics(tick, &simulation, &event_counts);
+            }
+        }
+    }
+
+    print_statistics(config.ticks, &simulation, &event_counts);
+
+    if config.system_stats {
+        print_system_stats(&mut simulation);
+    }
+
+    Ok(())
+}
+
+// Create the starting simulation from a save file, a scenario or an empty world
+fn build_simulation(config: &HeadlessConfig) -> io::Result<Simulation> {
+    if let Some(path) = &config.load_path {
+        let mut saved_game = save::load_game_state(Path::new(path))?;
+        if let Some(seed) = config.seed {
+            saved_game.environment.set_seed(seed);
+        }
+        return Ok(saved_game.to_simulation());
+    }
+
+    if let Some(path) = &config.scenario_path {
+        let mut scenario = scenario::load_scenario(Path::new(path))?;
+        if config.seed.is_some() {
+            scenario.seed = config.seed;
+        }
+        return Ok(scenario.to_simulation());
+    }
+
+    let environment = match config.seed {
+        Some(seed) => Environment::with_seed(config.width, config.height, seed),
+        None => Environment::new(config.width, config.height),
+    };
+    Ok(Simulation::new(environment))
+}
+
+fn print_statistics(tick: u64, simulation: &Simulation, event_counts: &EventCounts) {
+    println!("tick {} (seed {})", tick, simulation.environment_ref().get_seed());
+
+    for (i, (_, colony)) in simulation.get_colonies().iter().enumerate() {
+        let (ants, food_stored, max_ants, food_deliveries) = colony.get_statistics();
+        let position = colony.get_position();
+        println!(
+            "  colony #{} at ({:.1},{:.1}): ants={} max_ants={} food_stored={:.1} deliveries={} steering={:?}",
+            i, position.x, position.y, ants, max_ants, food_stored, food_deliveries, colony.get_steering()
+        );
+    }
+
+    let food_sources = simulation.get_food_sources();
+    if !food_sources.is_empty() {
+        let remaining: f32 = food_sources.iter().map(|source| source.food.remaining).sum();
+        println!("  food sources: {} ({:.1} food left)", food_sources.len(), remaining);
+    }
+
+    let environment = simulation.environment_ref();
+    let grid_width = environment.get_grid_width();
+    let food_cells: Vec<f32> = environment.get_grid().iter().enumerate()
+        .filter(|(_, &cell)| cell == CellType::Food)
+        .map(|(cell, _)| environment.get_food_amount(cell % grid_width, cell / grid_width))
+        .collect();
+    if !food_cells.is_empty() || event_counts.food_cells_depleted > 0 {
+        let remaining = food_cells.iter().fold(0.0, |total, amount| total + amount);
+        println!("  food cells: {} ({:.1} food left)", food_cells.len(), remaining);
+    }
+
+    let free_ants: Vec<_> = simulation.get_ants().into_iter()
+        .filter(|ant| ant.state.colony_id.is_none())
+        .collect();
+    if !free_ants.is_empty() {
+        let carrying = free_ants.iter().filter(|ant| ant.state.carrying_food).count();
+        println!("  free ants: {} ({} carrying food)", free_ants.len(), carrying);
+    }
+
+    println!(
+        "  events: food_picked_up={} food_delivered={} ants_spawned={} ants_died={} food_sources_depleted={} food_cells_depleted={}",
+        event_counts.food_picked_up, event_counts.food_delivered,
+        event_counts.ants_spawned, event_counts.ants_died, event_counts.food_sources_depleted,
+        event_counts.food_cells_depleted
+    );
+}
+
+fn print_system_stats(simulation: &mut Simulation) {
+    println!("systems:");
+    for system in simulation.world().systems() {
+        println!(
+            "  {:?} {}: runs={} avg={:.3}ms max={:.3}ms total={:.1}ms{}",
+            system.stage,
+            system.name,
+            system.stats.runs,
+            system.stats.average().as_secs_f64() * 1000.0,
+            system.stats.max.as_secs_f64() * 1000.0,
+            system.stats.total.as_secs_f64() * 1000.0,
+            if system.enabled { "" } else { " (disabled)" }
+        );
+    }
+}
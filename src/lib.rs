@@ -0,0 +1,29 @@
+//! Ant Simulacrum simulation library.
+//!
+//! The simulation core (ants, colonies, environment, pheromones, ECS, save
+//! files, data export and the headless runner) has no dependency on a window and can be
+//! used on its own by batch runners, tests and analysis tools. The SFML
+//! front-end (`render`, `game` and `ui`) is only built with the `render`
+//! feature, which is enabled by default.
+
+pub mod ant;
+pub mod colony;
+pub mod ecs;
+pub mod environment;
+pub mod events;
+pub mod export;
+pub mod headless;
+pub mod math;
+pub mod pheromone;
+pub mod rng;
+pub mod save;
+pub mod scenario;
+pub mod simulation;
+pub mod timestep;
+
+#[cfg(feature = "render")]
+pub mod game;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "render")]
+pub mod ui;
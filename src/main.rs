@@ -1,31 +1,53 @@
-extern crate sfml;
-extern crate rand;
-extern crate serde;
-extern crate serde_json;
-extern crate simple_logger;
-
-mod ant;
-mod colony;
-mod environment;
-mod game;
-mod pheromone;
-mod ui;
-mod ecs;
-mod save;
-
-use game::Game;
+use ant_simulacrum::headless::{self, HeadlessConfig};
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // Without the SFML front-end the headless runner is the only option
+    if HeadlessConfig::is_requested(&args) || !cfg!(feature = "render") {
+        run_headless(&args);
+        return;
+    }
+
+    #[cfg(feature = "render")]
+    run_game();
+}
+
+fn run_headless(args: &[String]) {
+    let config = match HeadlessConfig::from_args(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    simple_logger::SimpleLogger::new()
+        .with_level(config.log_level)
+        .with_utc_timestamps()
+        .init()
+        .unwrap();
+
+    if let Err(e) = headless::run(&config) {
+        eprintln!("Headless run failed: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(feature = "render")]
+fn run_game() {
+    use ant_simulacrum::game::Game;
+
     // Initialize logger with timestamp and debug level
     simple_logger::SimpleLogger::new()
         .with_level(log::LevelFilter::Debug)
         .with_utc_timestamps()
         .init()
         .unwrap();
-    
+
     log::info!("Starting Ant Simulacrum");
-    
+
     // Create a new game instance and run it
     let mut game = Game::new(1200, 800, "Ant Simulacrum");
     game.run();
-}
\ No newline at end of file
+}
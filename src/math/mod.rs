@@ -0,0 +1,16 @@
+use serde::{Serialize, Deserialize};
+
+// Plain 2D vector used throughout the simulation core.
+// The core keeps its own type so it doesn't depend on SFML;
+// the render layer converts it to SFML's vector when drawing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Vector2f {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vector2f {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+}
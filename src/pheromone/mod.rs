@@ -1,22 +1,199 @@
-use sfml::graphics::{RenderWindow, RenderTarget, CircleShape, Color, Transformable, BlendMode, RenderStates, Shape};
-use sfml::system::Vector2f;
-use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+use crate::ecs::entity::EntityId;
+use crate::math::Vector2f;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum PheromoneType {
     Food,
     Home,
+    Alarm, // Left by ants that are hurt or run into an intruder
+}
+
+impl PheromoneType {
+    // Every pheromone type, in declaration order
+    pub const ALL: [PheromoneType; 3] = [PheromoneType::Food, PheromoneType::Home, PheromoneType::Alarm];
+    
+    // Number of pheromone types
+    pub const COUNT: usize = Self::ALL.len();
+    
+    // Position of this type in per-type tables, e.g. the pheromone grids
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// How a pheromone's strength fades over time
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum DecayModel {
+    // Lose a fixed amount of strength per second
+    Linear { rate: f32 },
+    // Lose a fixed share of the remaining strength per second
+    Exponential { rate: f32 },
+    // Lose half of the remaining strength every `half_life` seconds
+    HalfLife { half_life: f32 },
+}
+
+impl DecayModel {
+    // Strength left after `delta_time` seconds of decay
+    pub fn apply(&self, strength: f32, delta_time: f32) -> f32 {
+        match *self {
+            DecayModel::Linear { rate } => strength - rate * delta_time,
+            DecayModel::Exponential { rate } => strength * (-rate * delta_time).exp(),
+            DecayModel::HalfLife { half_life } if half_life > 0.0 => strength * 0.5_f32.powf(delta_time / half_life),
+            DecayModel::HalfLife { .. } => 0.0,
+        }
+    }
+}
+
+// Settings for one pheromone type
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PheromoneConfig {
+    pub decay: DecayModel,
+    // Cells at or below this strength are cleared
+    pub removal_threshold: f32,
+    // Deposits and diffusion never raise a cell above this strength
+    pub max_strength: f32,
+}
+
+impl PheromoneConfig {
+    // The settings a pheromone type starts with
+    pub fn for_type(pheromone_type: PheromoneType) -> Self {
+        match pheromone_type {
+            PheromoneType::Food | PheromoneType::Home => Self::default(),
+            // Alarms only matter while the threat is there, so they fade within seconds
+            PheromoneType::Alarm => Self {
+                decay: DecayModel::HalfLife { half_life: 1.0 },
+                removal_threshold: 0.01,
+                max_strength: 1.0,
+            },
+        }
+    }
+}
+
+impl Default for PheromoneConfig {
+    fn default() -> Self {
+        Self {
+            decay: DecayModel::Linear { rate: 0.005 },
+            removal_threshold: 0.001,
+            max_strength: 1.0,
+        }
+    }
+}
+
+// Wind that blows pheromone across the map, in pixels per second
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum Wind {
+    // No wind
+    #[default]
+    Calm,
+    // The same wind everywhere
+    Uniform { x: f32, y: f32 },
+    // A `columns` x `rows` grid of wind vectors stretched over the map, row by
+    // row from the top left; each vector covers its part of the map
+    Field { columns: usize, rows: usize, vectors: Vec<Vector2f> },
+}
+
+impl Wind {
+    // Wind at a point given as a share (0 to 1) of the map's width and height
+    pub fn at(&self, x: f32, y: f32) -> Vector2f {
+        match self {
+            Wind::Calm => Vector2f::default(),
+            Wind::Uniform { x, y } => Vector2f::new(*x, *y),
+            Wind::Field { columns, rows, vectors } => {
+                if *columns == 0 || *rows == 0 {
+                    return Vector2f::default();
+                }
+                
+                let column = ((x * *columns as f32) as usize).min(columns - 1);
+                let row = ((y * *rows as f32) as usize).min(rows - 1);
+                vectors.get(row * columns + column).copied().unwrap_or_default()
+            }
+        }
+    }
+    
+    // Whether the wind is still everywhere
+    pub fn is_calm(&self) -> bool {
+        match self {
+            Wind::Calm => true,
+            Wind::Uniform { x, y } => *x == 0.0 && *y == 0.0,
+            Wind::Field { vectors, .. } => vectors.iter().all(|vector| vector.x == 0.0 && vector.y == 0.0),
+        }
+    }
 }
 
+// Pheromones are kept in channels: one per colony, plus a shared channel for
+// pheromones that aren't laid by a colony, such as the scent of food sources.
+// Ants sense the shared channel and their own colony's, unless cross-colony
+// sensing is on, in which case they sense every channel.
 pub struct PheromoneSystem {
-    // Grid-based pheromone storage
-    // Key: (grid_x, grid_y, pheromone_type)
-    // Value: strength (0.0 to 1.0)
-    pheromones: HashMap<(usize, usize, PheromoneType), f32>,
+    // The shared channel comes first, colony channels are added when first used
+    channels: Vec<PheromoneChannel>,
+    // Decay and limits for each pheromone type, indexed by `PheromoneType::index`
+    configs: [PheromoneConfig; PheromoneType::COUNT],
     grid_size: f32,
     width: usize,
     height: usize,
+    // Share of the difference to each neighbouring cell that spreads per second; 0 turns diffusion off
+    diffusion_rate: f32,
+    // Whether ants sense other colonies' pheromones as well as their own
+    cross_colony: bool,
+    wind: Wind,
+    // Cells pheromone can't enter, e.g. walls. The environment keeps it in step with its grid
+    barriers: Vec<bool>,
+    // Share of each cell the wind moves along x and y per tick, and the tick length it was
+    // worked out for; None once the wind or the grid changes
+    wind_shares: Vec<(f32, f32)>,
+    wind_shares_delta_time: Option<f32>,
+    // Copy of a grid from before the diffusion step, kept to avoid reallocating it every tick
+    scratch: Vec<f32>,
+}
+
+// The pheromones laid by one colony, or the shared ones for `colony: None`
+struct PheromoneChannel {
+    colony: Option<EntityId>,
+    // Grid-based pheromone storage: one dense grid per pheromone type,
+    // indexed by `PheromoneType::index`, each cell at `grid_y * width + grid_x`.
+    // Strength ranges from 0.0 (none) to the type's max strength.
+    grids: Vec<Vec<f32>>,
+    // Whether each type may have pheromone anywhere on the grid, so ants can skip sensing for it
+    present: [bool; PheromoneType::COUNT],
+}
+
+impl PheromoneChannel {
+    fn new(colony: Option<EntityId>, cells: usize) -> Self {
+        Self {
+            colony,
+            grids: PheromoneType::ALL.iter().map(|_| vec![0.0; cells]).collect(),
+            present: [false; PheromoneType::COUNT],
+        }
+    }
+}
+
+// Larger steps would make the explicit diffusion step overshoot and oscillate
+const MAX_DIFFUSION_STEP: f32 = 0.25;
+
+// Most of a cell's pheromone the wind moves along each axis per tick; with
+// both axes at the limit the cell is emptied but never goes negative
+const MAX_ADVECTION_STEP: f32 = 0.5;
+
+// Keeps gradient samples at the far edges inside the last cell
+const GRID_EDGE_MARGIN: f32 = 0.001;
+
+// Pheromone sensed by `PheromoneSystem::sense_antennae` at the left antenna,
+// straight ahead and at the right antenna
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AntennaReading {
+    pub left: f32,
+    pub centre: f32,
+    pub right: f32,
 }
 
 impl PheromoneSystem {
@@ -25,107 +202,636 @@ impl PheromoneSystem {
         let height_cells = (height as f32 / grid_size) as usize;
         
         Self {
-            pheromones: HashMap::new(),
+            channels: vec![PheromoneChannel::new(None, width_cells * height_cells)],
+            configs: PheromoneType::ALL.map(PheromoneConfig::for_type),
             grid_size,
             width: width_cells,
             height: height_cells,
+            diffusion_rate: 0.0,
+            cross_colony: false,
+            wind: Wind::Calm,
+            barriers: vec![false; width_cells * height_cells],
+            wind_shares: Vec::new(),
+            wind_shares_delta_time: None,
+            scratch: Vec::new(),
         }
     }
     
-    pub fn add_pheromone(&mut self, x: f32, y: f32, pheromone_type: PheromoneType, strength: f32) {
-        let grid_x = (x / self.grid_size) as usize;
-        let grid_y = (y / self.grid_size) as usize;
+    // Add pheromone to the channel of `colony`, or to the shared channel for None
+    pub fn add_pheromone(&mut self, x: f32, y: f32, pheromone_type: PheromoneType, colony: Option<EntityId>, strength: f32) {
+        if let Some(cell) = self.cell_at(x, y) {
+            self.add_to_cell(cell, pheromone_type, colony, strength);
+        }
+    }
+    
+    // Raise the pheromone at a position to at least `strength`. Unlike
+    // `add_pheromone`, crossing trails don't pile up, so a trail stays
+    // strongest where it is freshest.
+    pub fn mark_pheromone(&mut self, x: f32, y: f32, pheromone_type: PheromoneType, colony: Option<EntityId>, strength: f32) {
+        if let Some(cell) = self.cell_at(x, y) {
+            let max_strength = self.configs[pheromone_type.index()].max_strength;
+            let channel = self.channel_mut(colony);
+            let current_strength = &mut channel.grids[pheromone_type.index()][cell];
+            *current_strength = current_strength.max(strength.min(max_strength));
+            channel.present[pheromone_type.index()] = true;
+        }
+    }
+    
+    // The pheromone an ant of `colony` smells at a position: the strongest of the
+    // shared channel and its colony's, or of every channel with cross-colony sensing
+    pub fn get_pheromone(&self, x: f32, y: f32, pheromone_type: &PheromoneType, colony: Option<EntityId>) -> f32 {
+        let Some(cell) = self.cell_at(x, y) else {
+            return 0.0;
+        };
         
-        if grid_x >= self.width || grid_y >= self.height {
-            return;
+        self.sensed_at(cell, *pheromone_type, self.own_channel(colony))
+    }
+    
+    // Like `get_pheromone`, but interpolated between the centres of the four
+    // nearest cells, so the strength changes smoothly as an ant moves within a cell
+    pub fn sample_pheromone(&self, x: f32, y: f32, pheromone_type: PheromoneType, colony: Option<EntityId>) -> f32 {
+        if x < 0.0 || y < 0.0 || self.cell_at(x, y).is_none() {
+            return 0.0;
         }
         
-        let key = (grid_x, grid_y, pheromone_type);
-        let current_strength = self.pheromones.get(&key).unwrap_or(&0.0);
+        let own_channel = self.own_channel(colony);
         
-        // Pheromones add up to a maximum
-        let new_strength = (current_strength + strength).min(1.0);
-        self.pheromones.insert(key, new_strength);
+        // Position relative to the cell centres; cells past the edges repeat the edge cells
+        let fx = (x / self.grid_size - 0.5).clamp(0.0, (self.width - 1) as f32);
+        let fy = (y / self.grid_size - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (fx as usize, fy as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+        
+        let at = |grid_x: usize, grid_y: usize| self.sensed_at(grid_y * self.width + grid_x, pheromone_type, own_channel);
+        let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+        let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
     }
     
-    pub fn get_pheromone(&self, x: f32, y: f32, pheromone_type: &PheromoneType) -> f32 {
-        let grid_x = (x / self.grid_size) as usize;
-        let grid_y = (y / self.grid_size) as usize;
-        
-        if grid_x >= self.width || grid_y >= self.height {
-            return 0.0;
+    // Direction and steepness of the smoothly sampled pheromone at a position, in
+    // strength per pixel. It points toward stronger pheromone and is zero off the grid.
+    pub fn gradient(&self, x: f32, y: f32, pheromone_type: PheromoneType, colony: Option<EntityId>) -> Vector2f {
+        if x < 0.0 || y < 0.0 || self.cell_at(x, y).is_none() {
+            return Vector2f::new(0.0, 0.0);
         }
         
-        *self.pheromones.get(&(grid_x, grid_y, pheromone_type.clone())).unwrap_or(&0.0)
+        // Central differences one cell either side, kept on the grid at the edges
+        let max_x = self.width as f32 * self.grid_size - GRID_EDGE_MARGIN;
+        let max_y = self.height as f32 * self.grid_size - GRID_EDGE_MARGIN;
+        let (left, right) = ((x - self.grid_size).max(0.0), (x + self.grid_size).min(max_x));
+        let (up, down) = ((y - self.grid_size).max(0.0), (y + self.grid_size).min(max_y));
+        
+        let sample = |x: f32, y: f32| self.sample_pheromone(x, y, pheromone_type, colony);
+        let dx = if right > left { (sample(right, y) - sample(left, y)) / (right - left) } else { 0.0 };
+        let dy = if down > up { (sample(x, down) - sample(x, up)) / (down - up) } else { 0.0 };
+        Vector2f::new(dx, dy)
+    }
+    
+    // Sample pheromone the way an ant's antennae would: `distance` ahead of
+    // `position` along `direction`, and `spread` radians to either side of it.
+    // Screen y points down, so the left antenna is at `direction - spread`
+    // and turning toward it lowers the ant's direction.
+    pub fn sense_antennae(
+        &self,
+        position: Vector2f,
+        direction: f32,
+        spread: f32,
+        distance: f32,
+        pheromone_type: PheromoneType,
+        colony: Option<EntityId>,
+    ) -> AntennaReading {
+        let sample = |angle: f32| {
+            self.sample_pheromone(position.x + angle.cos() * distance, position.y + angle.sin() * distance, pheromone_type, colony)
+        };
+        
+        AntennaReading {
+            left: sample(direction - spread),
+            centre: sample(direction),
+            right: sample(direction + spread),
+        }
+    }
+    
+    // Whether an ant of `colony` could smell pheromone of a type anywhere. False means there is none at all.
+    pub fn has_pheromone(&self, pheromone_type: PheromoneType, colony: Option<EntityId>) -> bool {
+        self.sensed_channels(colony).any(|channel| channel.present[pheromone_type.index()])
     }
     
     pub fn update(&mut self, delta_time: f32) {
-        // Evaporation rate controls how quickly pheromones fade
-        // Current rate: 0.04 * delta_time (4% per second if delta_time is in seconds)
-        // Decrease this value for longer-lasting pheromones that create more persistent trails
-        let evaporation_rate = 0.005 * delta_time; // Reduced for much longer-lasting pheromones
-        
-        let mut to_remove = Vec::new();
-        
-        for (key, strength) in self.pheromones.iter_mut() {
-            // Reduce strength of each pheromone by the evaporation rate
-            *strength -= evaporation_rate;
-            
-            // Threshold for removing weak pheromones (currently 0.003)
-            // Decrease this value to allow weaker pheromones to persist longer
-            if *strength <= 0.001 { // Lower threshold for pheromone removal
-                to_remove.push(*key);
+        for channel in self.channels.iter_mut() {
+            // Each type evaporates by its own decay model, see `PheromoneConfig`
+            for ((grid, config), present) in channel.grids.iter_mut().zip(&self.configs).zip(&mut channel.present) {
+                if !*present {
+                    continue;
+                }
+                
+                *present = false;
+                for strength in grid.iter_mut().filter(|strength| **strength > 0.0) {
+                    *strength = config.decay.apply(*strength, delta_time);
+                    
+                    // Clear weak pheromones so they don't linger forever
+                    if *strength <= config.removal_threshold {
+                        *strength = 0.0;
+                    } else {
+                        *present = true;
+                    }
+                }
             }
         }
+    }
+    
+    pub fn get_config(&self, pheromone_type: PheromoneType) -> &PheromoneConfig {
+        &self.configs[pheromone_type.index()]
+    }
+    
+    // Change how a pheromone type decays. Takes effect from the next update;
+    // cells above a lowered max strength are clipped right away.
+    pub fn set_config(&mut self, pheromone_type: PheromoneType, config: PheromoneConfig) {
+        self.configs[pheromone_type.index()] = config;
+        for channel in self.channels.iter_mut() {
+            for strength in channel.grids[pheromone_type.index()].iter_mut() {
+                *strength = strength.min(config.max_strength);
+            }
+        }
+    }
+    
+    // Mark which cells pheromone can't enter, e.g. walls, from `is_barrier(grid_x, grid_y)`
+    pub fn set_barriers(&mut self, is_barrier: impl Fn(usize, usize) -> bool) {
+        let width = self.width;
+        self.barriers = (0..self.width * self.height).map(|cell| is_barrier(cell % width, cell / width)).collect();
+    }
+    
+    // Mark whether pheromone can enter a single cell
+    pub fn set_barrier(&mut self, grid_x: usize, grid_y: usize, barrier: bool) {
+        if grid_x < self.width && grid_y < self.height {
+            self.barriers[grid_y * self.width + grid_x] = barrier;
+        }
+    }
+    
+    // Spread pheromone into neighbouring cells so trails can be sensed from further away.
+    // Barrier cells take in none and give none away; pheromone laid there before the
+    // cell became a barrier stays put and fades as usual. Does nothing while the diffusion rate is 0.
+    pub fn diffuse(&mut self, delta_time: f32) {
+        let step = (self.diffusion_rate * delta_time).min(MAX_DIFFUSION_STEP);
+        if step <= 0.0 {
+            return;
+        }
         
-        // Remove all pheromones that fell below the threshold
-        for key in to_remove {
-            self.pheromones.remove(&key);
+        let (width, height) = (self.width, self.height);
+        let blocked = &self.barriers;
+        
+        for channel in self.channels.iter_mut() {
+            for ((grid, config), present) in channel.grids.iter_mut().zip(&self.configs).zip(&channel.present) {
+                if !*present {
+                    continue;
+                }
+                
+                self.scratch.clear();
+                self.scratch.extend_from_slice(grid);
+                let before = &self.scratch;
+                
+                for y in 0..height {
+                    for x in 0..width {
+                        let cell = y * width + x;
+                        if blocked[cell] {
+                            continue;
+                        }
+                        
+                        // Exchange with each open neighbour; the flow between two
+                        // cells is symmetric, so no pheromone is created or lost
+                        let mut flow = 0.0;
+                        let neighbours = [
+                            (x > 0).then(|| cell - 1),
+                            (x + 1 < width).then(|| cell + 1),
+                            (y > 0).then(|| cell - width),
+                            (y + 1 < height).then(|| cell + width),
+                        ];
+                        for neighbour in neighbours.into_iter().flatten() {
+                            if !blocked[neighbour] {
+                                flow += before[neighbour] - before[cell];
+                            }
+                        }
+                        
+                        grid[cell] = (before[cell] + step * flow).min(config.max_strength);
+                    }
+                }
+            }
         }
     }
     
-    pub fn render(&self, window: &mut RenderWindow) {
-        for ((grid_x, grid_y, pheromone_type), strength) in &self.pheromones {
-            let x = *grid_x as f32 * self.grid_size;
-            let y = *grid_y as f32 * self.grid_size;
-            
-            let mut pheromone = CircleShape::new(self.grid_size / 2.0, 8);
-            pheromone.set_position(Vector2f::new(x, y));
-            
-            // Enhance alpha value to make pheromones more visible
-            let alpha = (*strength * 255.0).min(255.0) as u8;
-            
-            match pheromone_type {
-                PheromoneType::Food => {
-                    // Brighter green for food pheromones
-                    pheromone.set_fill_color(Color::rgba(0, 255, 100, alpha));
+    // Blow pheromone downwind. Each cell passes the share of its pheromone
+    // the wind carries across a cell this tick to its downwind neighbours,
+    // one along each axis. Pheromone blown off the edge of the map is lost.
+    // Barrier cells neither take in pheromone nor pass it on, so walls
+    // shelter the cells upwind of them.
+    pub fn advect(&mut self, delta_time: f32) {
+        if delta_time <= 0.0 || self.wind.is_calm() {
+            return;
+        }
+        
+        let (width, height) = (self.width, self.height);
+        if self.wind_shares_delta_time != Some(delta_time) {
+            self.update_wind_shares(delta_time);
+        }
+        let blocked = &self.barriers;
+        let shares = &self.wind_shares;
+        
+        for channel in self.channels.iter_mut() {
+            for ((grid, config), present) in channel.grids.iter_mut().zip(&self.configs).zip(&channel.present) {
+                if !*present {
+                    continue;
                 }
-                PheromoneType::Home => {
-                    // Brighter magenta for home pheromones
-                    pheromone.set_fill_color(Color::rgba(255, 50, 255, alpha));
+                
+                self.scratch.clear();
+                self.scratch.extend_from_slice(grid);
+                let before = &self.scratch;
+                
+                for y in 0..height {
+                    for x in 0..width {
+                        let cell = y * width + x;
+                        if blocked[cell] || before[cell] <= 0.0 {
+                            continue;
+                        }
+                        
+                        let (share_x, share_y) = shares[cell];
+                        let downwind = [
+                            (share_x, if share_x > 0.0 { (x + 1 < width).then(|| cell + 1) } else { (x > 0).then(|| cell - 1) }),
+                            (share_y, if share_y > 0.0 { (y + 1 < height).then(|| cell + width) } else { (y > 0).then(|| cell - width) }),
+                        ];
+                        
+                        for (share, target) in downwind {
+                            let moved = before[cell] * share.abs();
+                            match target {
+                                Some(target) if blocked[target] => {}
+                                Some(target) => {
+                                    grid[cell] -= moved;
+                                    grid[target] += moved;
+                                }
+                                None => grid[cell] -= moved,
+                            }
+                        }
+                    }
+                }
+                
+                // Pheromone piling up against a wall stops at the type's max
+                for strength in grid.iter_mut() {
+                    *strength = strength.clamp(0.0, config.max_strength);
+                }
+            }
+        }
+    }
+    
+    pub fn get_wind(&self) -> &Wind {
+        &self.wind
+    }
+    
+    pub fn set_wind(&mut self, wind: Wind) {
+        self.wind = wind;
+        self.wind_shares_delta_time = None;
+    }
+    
+    // Work out the share of each cell moved along x and y per tick of `delta_time`;
+    // negative shares move toward the top left
+    fn update_wind_shares(&mut self, delta_time: f32) {
+        let (width, height) = (self.width, self.height);
+        let (wind, grid_size) = (&self.wind, self.grid_size);
+        let share = |speed: f32| (speed * delta_time / grid_size).clamp(-MAX_ADVECTION_STEP, MAX_ADVECTION_STEP);
+        
+        self.wind_shares = (0..width * height)
+            .map(|cell| {
+                let wind = wind.at(
+                    ((cell % width) as f32 + 0.5) / width as f32,
+                    ((cell / width) as f32 + 0.5) / height as f32,
+                );
+                (share(wind.x), share(wind.y))
+            })
+            .collect();
+        self.wind_shares_delta_time = Some(delta_time);
+    }
+    
+    pub fn get_diffusion_rate(&self) -> f32 {
+        self.diffusion_rate
+    }
+    
+    pub fn set_diffusion_rate(&mut self, diffusion_rate: f32) {
+        self.diffusion_rate = diffusion_rate.max(0.0);
+    }
+    
+    pub fn get_cross_colony_sensing(&self) -> bool {
+        self.cross_colony
+    }
+    
+    // Let ants smell other colonies' pheromones as well as their own
+    pub fn set_cross_colony_sensing(&mut self, cross_colony: bool) {
+        self.cross_colony = cross_colony;
+    }
+    
+    // Change the size of the area covered, in pixels. Pheromone in cells that
+    // are inside both the old and the new area is kept, the rest is dropped.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let new_width = (width as f32 / self.grid_size) as usize;
+        let new_height = (height as f32 / self.grid_size) as usize;
+        
+        for channel in self.channels.iter_mut() {
+            for grid in channel.grids.iter_mut() {
+                let mut new_grid = vec![0.0; new_width * new_height];
+                for y in 0..self.height.min(new_height) {
+                    for x in 0..self.width.min(new_width) {
+                        new_grid[y * new_width + x] = grid[y * self.width + x];
+                    }
                 }
+                *grid = new_grid;
             }
-            
-            let mut states = RenderStates::default();
-            states.blend_mode = BlendMode::ADD;
-            window.draw_with_renderstates(&pheromone, &states);
         }
+        
+        // The environment marks the walls of the new grid again
+        self.barriers = vec![false; new_width * new_height];
+        self.wind_shares_delta_time = None;
+        
+        self.width = new_width;
+        self.height = new_height;
+    }
+    
+    // Size of one pheromone cell in pixels
+    pub fn get_grid_size(&self) -> f32 {
+        self.grid_size
     }
     
-    pub fn get_all_pheromones(&self) -> &HashMap<(usize, usize, PheromoneType), f32> {
-        &self.pheromones
+    // Width and height of the pheromone grid in cells
+    pub fn get_grid_dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
     }
     
-    pub fn add_pheromone_at_grid(&mut self, grid_x: usize, grid_y: usize, pheromone_type: PheromoneType, strength: f32) {
+    // Every cell that holds some pheromone, as ((grid_x, grid_y, type), colony, strength).
+    // The colony is None for the shared channel.
+    pub fn get_all_pheromones(&self) -> impl Iterator<Item = ((usize, usize, PheromoneType), Option<EntityId>, f32)> + '_ {
+        let width = self.width;
+        self.channels.iter().flat_map(move |channel| {
+            PheromoneType::ALL.into_iter().flat_map(move |pheromone_type| {
+                channel.grids[pheromone_type.index()].iter()
+                    .enumerate()
+                    .filter(|(_, strength)| **strength > 0.0)
+                    .map(move |(cell, strength)| ((cell % width, cell / width, pheromone_type), channel.colony, *strength))
+            })
+        })
+    }
+    
+    pub fn add_pheromone_at_grid(
+        &mut self,
+        grid_x: usize,
+        grid_y: usize,
+        pheromone_type: PheromoneType,
+        colony: Option<EntityId>,
+        strength: f32,
+    ) {
         if grid_x >= self.width || grid_y >= self.height {
             return;
         }
         
-        let key = (grid_x, grid_y, pheromone_type);
-        let current_strength = self.pheromones.get(&key).unwrap_or(&0.0);
+        self.add_to_cell(grid_y * self.width + grid_x, pheromone_type, colony, strength);
+    }
+    
+    fn add_to_cell(&mut self, cell: usize, pheromone_type: PheromoneType, colony: Option<EntityId>, strength: f32) {
+        let max_strength = self.configs[pheromone_type.index()].max_strength;
+        let channel = self.channel_mut(colony);
+        let current_strength = &mut channel.grids[pheromone_type.index()][cell];
         
         // Pheromones add up to a maximum
-        let new_strength = (current_strength + strength).min(1.0);
-        self.pheromones.insert(key, new_strength);
+        *current_strength = (*current_strength + strength).min(max_strength);
+        channel.present[pheromone_type.index()] = true;
+    }
+    
+    // Pheromone of one type that an ant smells in a cell, given the index of its
+    // colony's channel from `own_channel`
+    fn sensed_at(&self, cell: usize, pheromone_type: PheromoneType, own_channel: Option<usize>) -> f32 {
+        if self.cross_colony {
+            return self.channels.iter()
+                .map(|channel| channel.grids[pheromone_type.index()][cell])
+                .fold(0.0, f32::max);
+        }
+        
+        // Ants sample many cells every tick, so look at the two channels directly
+        let shared = self.channels[0].grids[pheromone_type.index()][cell];
+        match own_channel {
+            Some(index) => shared.max(self.channels[index].grids[pheromone_type.index()][cell]),
+            None => shared,
+        }
+    }
+    
+    // Index of the channel `colony` lays pheromone in, if it has laid any yet
+    fn own_channel(&self, colony: Option<EntityId>) -> Option<usize> {
+        colony.and_then(|colony| self.channels.iter().position(|channel| channel.colony == Some(colony)))
+    }
+    
+    // The channels an ant of `colony` can smell
+    fn sensed_channels(&self, colony: Option<EntityId>) -> impl Iterator<Item = &PheromoneChannel> + '_ {
+        let cross_colony = self.cross_colony;
+        self.channels.iter()
+            .filter(move |channel| cross_colony || channel.colony.is_none() || channel.colony == colony)
+    }
+    
+    // The channel of `colony`, created the first time the colony lays pheromone
+    fn channel_mut(&mut self, colony: Option<EntityId>) -> &mut PheromoneChannel {
+        let index = match self.channels.iter().position(|channel| channel.colony == colony) {
+            Some(index) => index,
+            None => {
+                self.channels.push(PheromoneChannel::new(colony, self.width * self.height));
+                self.channels.len() - 1
+            }
+        };
+        &mut self.channels[index]
     }
-} 
\ No newline at end of file
+    
+    // Index of the cell under a screen position, if it is on the grid
+    fn cell_at(&self, x: f32, y: f32) -> Option<usize> {
+        let grid_x = (x / self.grid_size) as usize;
+        let grid_y = (y / self.grid_size) as usize;
+        
+        if grid_x >= self.width || grid_y >= self.height {
+            return None;
+        }
+        
+        Some(grid_y * self.width + grid_x)
+    }
+}
+
+// Read per-type pheromone settings from a JSON file, e.g.
+// `{ "Food": { "decay": { "model": "half_life", "half_life": 60.0 } } }`
+pub fn load_pheromone_configs(path: &Path) -> io::Result<BTreeMap<PheromoneType, PheromoneConfig>> {
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    
+    let configs = serde_json::from_str(&contents)?;
+    Ok(configs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // A 10x10 cell grid with 10 pixel cells
+    fn small_system() -> PheromoneSystem {
+        PheromoneSystem::new(100, 100, 10.0)
+    }
+    
+    fn total(system: &PheromoneSystem, pheromone_type: PheromoneType) -> f32 {
+        system.get_all_pheromones()
+            .filter(|((_, _, cell_type), _, _)| *cell_type == pheromone_type)
+            .map(|(_, _, strength)| strength)
+            .sum()
+    }
+    
+    fn at_cell(system: &PheromoneSystem, grid_x: usize, grid_y: usize) -> f32 {
+        system.get_pheromone((grid_x as f32 + 0.5) * 10.0, (grid_y as f32 + 0.5) * 10.0, &PheromoneType::Food, None)
+    }
+    
+    #[test]
+    fn diffusion_conserves_pheromone_without_walls() {
+        let mut system = small_system();
+        system.set_diffusion_rate(2.0);
+        system.add_pheromone_at_grid(4, 4, PheromoneType::Food, None, 0.8);
+        system.add_pheromone_at_grid(0, 9, PheromoneType::Food, None, 0.5);
+        
+        for _ in 0..200 {
+            system.diffuse(1.0 / 60.0);
+        }
+        
+        assert!((total(&system, PheromoneType::Food) - 1.3).abs() < 1e-4);
+        // It did spread
+        assert!(at_cell(&system, 4, 4) < 0.8);
+        assert!(at_cell(&system, 5, 4) > 0.0);
+    }
+    
+    #[test]
+    fn diffusion_never_enters_walls() {
+        let mut system = small_system();
+        system.set_diffusion_rate(2.0);
+        system.set_barriers(|x, _| x == 5);
+        system.add_pheromone_at_grid(4, 4, PheromoneType::Food, None, 0.8);
+        
+        for _ in 0..600 {
+            system.diffuse(1.0 / 60.0);
+        }
+        
+        for y in 0..10 {
+            assert_eq!(at_cell(&system, 5, y), 0.0);
+            // The wall runs the full height, so nothing gets past it either
+            assert_eq!(at_cell(&system, 6, y), 0.0);
+        }
+        assert!((total(&system, PheromoneType::Food) - 0.8).abs() < 1e-4);
+    }
+    
+    #[test]
+    fn pheromone_under_a_new_wall_stays_put() {
+        let mut system = small_system();
+        system.set_diffusion_rate(2.0);
+        system.add_pheromone_at_grid(5, 4, PheromoneType::Food, None, 0.8);
+        system.set_barrier(5, 4, true);
+        
+        for _ in 0..60 {
+            system.diffuse(1.0 / 60.0);
+        }
+        
+        assert_eq!(at_cell(&system, 5, 4), 0.8);
+        assert_eq!(at_cell(&system, 4, 4), 0.0);
+    }
+    
+    #[test]
+    fn wind_never_blows_pheromone_into_walls() {
+        let mut system = small_system();
+        system.set_wind(Wind::Uniform { x: 20.0, y: 0.0 });
+        system.set_barrier(5, 4, true);
+        system.add_pheromone_at_grid(3, 4, PheromoneType::Food, None, 0.8);
+        
+        for _ in 0..120 {
+            system.advect(1.0 / 60.0);
+        }
+        
+        assert_eq!(at_cell(&system, 5, 4), 0.0);
+        assert_eq!(at_cell(&system, 6, 4), 0.0);
+        assert!(at_cell(&system, 4, 4) > 0.0);
+    }
+    
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "expected {}, got {}", expected, actual);
+    }
+    
+    #[test]
+    fn linear_decay_loses_a_fixed_amount() {
+        let decay = DecayModel::Linear { rate: 0.1 };
+        assert_close(decay.apply(1.0, 2.0), 0.8);
+        assert_close(decay.apply(0.5, 0.5), 0.45);
+    }
+    
+    #[test]
+    fn exponential_decay_loses_a_fixed_share() {
+        let decay = DecayModel::Exponential { rate: 0.5 };
+        // e^-1 of the strength is left after 2 seconds
+        assert_close(decay.apply(1.0, 2.0), 0.367_879);
+        // Two short steps leave the same as one long one
+        assert_close(decay.apply(decay.apply(0.8, 1.0), 1.0), decay.apply(0.8, 2.0));
+    }
+    
+    #[test]
+    fn half_life_decay_halves_every_half_life() {
+        let decay = DecayModel::HalfLife { half_life: 2.0 };
+        assert_close(decay.apply(0.8, 2.0), 0.4);
+        assert_close(decay.apply(0.8, 4.0), 0.2);
+        assert_close(decay.apply(0.8, 1.0), 0.8 / 2.0_f32.sqrt());
+        // Without a half-life pheromone is gone at once
+        assert_eq!(DecayModel::HalfLife { half_life: 0.0 }.apply(0.8, 0.1), 0.0);
+    }
+    
+    fn sample(system: &PheromoneSystem, x: f32, y: f32) -> f32 {
+        system.sample_pheromone(x, y, PheromoneType::Food, None)
+    }
+    
+    #[test]
+    fn sampling_interpolates_between_cell_centres() {
+        let mut system = small_system();
+        system.add_pheromone_at_grid(4, 4, PheromoneType::Food, None, 1.0);
+        
+        // The centre of the cell itself, then halfway to its neighbours
+        assert_close(sample(&system, 45.0, 45.0), 1.0);
+        assert_close(sample(&system, 50.0, 45.0), 0.5);
+        assert_close(sample(&system, 40.0, 45.0), 0.5);
+        assert_close(sample(&system, 45.0, 52.5), 0.25);
+        assert_close(sample(&system, 50.0, 50.0), 0.25);
+        assert_close(sample(&system, 55.0, 45.0), 0.0);
+    }
+    
+    #[test]
+    fn sampling_repeats_the_edge_cells_and_is_zero_off_the_grid() {
+        let mut system = small_system();
+        system.add_pheromone_at_grid(0, 0, PheromoneType::Food, None, 0.6);
+        
+        assert_close(sample(&system, 2.0, 2.0), 0.6);
+        assert_close(sample(&system, 2.0, 10.0), 0.3);
+        assert_eq!(sample(&system, -1.0, 2.0), 0.0);
+        assert_eq!(sample(&system, 2.0, 100.0), 0.0);
+    }
+    
+    #[test]
+    fn gradient_points_up_a_ramp() {
+        let mut system = small_system();
+        // Strength rises by 0.1 per cell to the right, the same in every row
+        for y in 0..10 {
+            for x in 1..10 {
+                system.add_pheromone_at_grid(x, y, PheromoneType::Food, None, 0.1 * x as f32);
+            }
+        }
+        
+        // 0.5 one cell right and 0.3 one cell left, 20 pixels apart
+        let slope = system.gradient(45.0, 45.0, PheromoneType::Food, None);
+        assert_close(slope.x, 0.01);
+        assert_close(slope.y, 0.0);
+        
+        // At the left edge the difference is taken from the edge of the grid: 0.1 over 15 pixels
+        let slope = system.gradient(5.0, 45.0, PheromoneType::Food, None);
+        assert_close(slope.x, 0.1 / 15.0);
+        
+        let slope = system.gradient(-5.0, 45.0, PheromoneType::Food, None);
+        assert_eq!((slope.x, slope.y), (0.0, 0.0));
+    }
+}
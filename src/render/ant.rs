@@ -0,0 +1,47 @@
+use sfml::graphics::{RenderWindow, RenderTarget, CircleShape, Color, Transformable, Shape};
+use sfml::system::Vector2f;
+
+use crate::simulation::AntRef;
+use super::Render;
+
+impl Render for AntRef<'_> {
+    fn render(&self, window: &mut RenderWindow) {
+        let position = self.position;
+        let direction = self.velocity.direction;
+        let carrying_food = self.state.carrying_food;
+        
+        // Create main body
+        let mut ant_body = CircleShape::new(5.0, 8);
+        ant_body.set_position(Vector2f::new(position.x - 5.0, position.y - 5.0));
+        
+        if carrying_food {
+            ant_body.set_fill_color(Color::rgb(255, 210, 0)); // Bright gold/yellow
+        } else {
+            ant_body.set_fill_color(Color::rgb(70, 70, 70)); // Darker grey
+        }
+        
+        // Create head to show direction
+        let mut ant_head = CircleShape::new(3.0, 6);
+        let head_x = position.x + direction.cos() * 7.0;
+        let head_y = position.y + direction.sin() * 7.0;
+        ant_head.set_position(Vector2f::new(head_x - 3.0, head_y - 3.0));
+        
+        // Also change head color based on state
+        if carrying_food {
+            ant_head.set_fill_color(Color::rgb(200, 100, 0)); // Orange head when carrying food
+        } else {
+            ant_head.set_fill_color(Color::rgb(20, 20, 20)); // Black head normally
+        }
+        
+        // Draw a small colored dot if ignoring pheromones
+        if self.state.ignore_pheromones_timer > 0.0 {
+            let mut indicator = CircleShape::new(2.0, 4);
+            indicator.set_position(Vector2f::new(position.x - 2.0, position.y - 2.0));
+            indicator.set_fill_color(Color::rgb(255, 0, 0)); // Red dot
+            window.draw(&indicator);
+        }
+        
+        window.draw(&ant_body);
+        window.draw(&ant_head);
+    }
+}
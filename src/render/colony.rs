@@ -0,0 +1,53 @@
+use sfml::graphics::{RenderWindow, RenderTarget, CircleShape, RectangleShape, Color, Transformable, Shape};
+use sfml::system::Vector2f;
+
+use crate::colony::Colony;
+use super::Render;
+
+impl Render for Colony {
+    fn render(&self, window: &mut RenderWindow) {
+        // Render food information above the colony
+        render_food_info(self, window);
+    }
+}
+
+fn render_food_info(colony: &Colony, window: &mut RenderWindow) {
+    // Text would need a font owned by the UI, so we use shapes instead:
+    // a colored circle with size proportional to the stored food
+    let position = colony.get_position();
+    
+    // Draw a circular indicator for stored food - gold/yellow
+    let radius = (colony.get_food_stored() / 10.0).min(8.0).max(2.0); // Size relative to food amount
+    let mut food_indicator = CircleShape::new(radius, 6);
+    food_indicator.set_fill_color(Color::rgb(255, 215, 0)); // Gold
+    food_indicator.set_position(Vector2f::new(
+        position.x - radius, 
+        position.y - 30.0 - radius // Position above colony
+    ));
+    window.draw(&food_indicator);
+    
+    // Draw a numerical indicator for deliveries - white background with number
+    let delivery_radius = 8.0;
+    let mut delivery_bg = CircleShape::new(delivery_radius, 12);
+    delivery_bg.set_fill_color(Color::rgb(255, 255, 255)); // White
+    delivery_bg.set_outline_thickness(1.0);
+    delivery_bg.set_outline_color(Color::rgb(0, 0, 0)); // Black outline
+    delivery_bg.set_position(Vector2f::new(
+        position.x + 15.0 - delivery_radius,
+        position.y - 30.0 - delivery_radius
+    ));
+    window.draw(&delivery_bg);
+    
+    // Draw a simple marker once the colony has received food
+    if colony.get_food_deliveries() > 0 {
+        let size = delivery_radius * 0.7;
+        let mut marker = RectangleShape::new();
+        marker.set_size(Vector2f::new(size, size));
+        marker.set_fill_color(Color::rgb(0, 0, 0)); // Black
+        marker.set_position(Vector2f::new(
+            position.x + 15.0 - size/2.0,
+            position.y - 30.0 - size/2.0
+        ));
+        window.draw(&marker);
+    }
+}
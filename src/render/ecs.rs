@@ -0,0 +1,58 @@
+use sfml::graphics::{RenderWindow, CircleShape, RectangleShape, Color, Transformable, Shape, RenderTarget};
+use sfml::system::Vector2f;
+
+use crate::ecs::component::{AppearanceComponent, PositionComponent, ShapeType};
+use crate::ecs::{System, World};
+
+/// Rendering system that draws all entities with appearance and position components
+pub struct RenderingSystem<'a> {
+    window: &'a mut RenderWindow,
+}
+
+impl<'a> RenderingSystem<'a> {
+    pub fn new(window: &'a mut RenderWindow) -> Self {
+        Self { window }
+    }
+}
+
+impl<'a> System for RenderingSystem<'a> {
+    fn update(&mut self, world: &mut World, _delta_time: f32) {
+        // Draw every entity that has both position and appearance components
+        for (position, appearance) in world.query_ref::<(&PositionComponent, &AppearanceComponent)>() {
+            // Draw the entity based on its appearance
+            match appearance.shape_type {
+                ShapeType::Circle => {
+                    let mut shape = CircleShape::new(appearance.radius, 32);
+                    shape.set_position(Vector2f::new(
+                        position.x - appearance.radius,
+                        position.y - appearance.radius,
+                    ));
+                    shape.set_fill_color(Color::rgb(
+                        appearance.color.0,
+                        appearance.color.1,
+                        appearance.color.2,
+                    ));
+                    self.window.draw(&shape);
+                }
+                ShapeType::Rectangle => {
+                    let mut shape = RectangleShape::new();
+                    shape.set_size(Vector2f::new(appearance.width, appearance.height));
+                    shape.set_position(Vector2f::new(
+                        position.x - appearance.width / 2.0,
+                        position.y - appearance.height / 2.0,
+                    ));
+                    shape.set_fill_color(Color::rgb(
+                        appearance.color.0,
+                        appearance.color.1,
+                        appearance.color.2,
+                    ));
+                    self.window.draw(&shape);
+                }
+            }
+        }
+    }
+    
+    fn name(&self) -> &str {
+        "RenderingSystem"
+    }
+}
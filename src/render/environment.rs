@@ -0,0 +1,47 @@
+use sfml::graphics::{RenderWindow, RenderTarget, RectangleShape, CircleShape, Color, Transformable, Shape};
+use sfml::system::Vector2f;
+
+use crate::environment::{Environment, CellType, CELL_SIZE};
+use super::Render;
+
+impl Render for Environment {
+    fn render(&self, window: &mut RenderWindow) {
+        // Render grid
+        for y in 0..self.get_grid_height() {
+            for x in 0..self.get_grid_width() {
+                let cell_type = self.get_cell(x, y);
+                
+                match cell_type {
+                    CellType::Empty => {},
+                    CellType::Wall => {
+                        let mut wall = RectangleShape::new();
+                        wall.set_size(Vector2f::new(CELL_SIZE, CELL_SIZE));
+                        wall.set_position(Vector2f::new(x as f32 * CELL_SIZE, y as f32 * CELL_SIZE));
+                        wall.set_fill_color(Color::rgb(100, 80, 60)); // Brown
+                        window.draw(&wall);
+                    },
+                    CellType::Food => {
+                        // Shrink food cells as ants carry their food off
+                        let full = self.get_food_config().amount_per_cell;
+                        let fullness = if full > 0.0 { (self.get_food_amount(x, y) / full).clamp(0.0, 1.0) } else { 1.0 };
+                        let radius = CELL_SIZE / 2.0 * (0.3 + 0.7 * fullness.sqrt());
+                        
+                        let mut food = CircleShape::new(radius, 6);
+                        food.set_position(Vector2f::new(
+                            (x as f32 + 0.5) * CELL_SIZE - radius,
+                            (y as f32 + 0.5) * CELL_SIZE - radius,
+                        ));
+                        food.set_fill_color(Color::rgb(50, 200, 50)); // Green
+                        window.draw(&food);
+                    },
+                    CellType::AntNest => {
+                        let mut nest = CircleShape::new(CELL_SIZE * 2.0, 32);
+                        nest.set_position(Vector2f::new((x as f32 - 1.5) * CELL_SIZE, (y as f32 - 1.5) * CELL_SIZE));
+                        nest.set_fill_color(Color::rgb(150, 100, 50)); // Brown
+                        window.draw(&nest);
+                    },
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use sfml::graphics::{RenderWindow, RenderTarget, CircleShape, Color, Transformable, Shape};
+use sfml::system::Vector2f;
+
+use crate::simulation::{FoodSourceRef, FOOD_SOURCE_RADIUS};
+use super::Render;
+
+impl Render for FoodSourceRef<'_> {
+    fn render(&self, window: &mut RenderWindow) {
+        let position = self.position;
+        
+        // Outline showing the size of the full source
+        let mut outline = CircleShape::new(FOOD_SOURCE_RADIUS, 16);
+        outline.set_position(Vector2f::new(position.x - FOOD_SOURCE_RADIUS, position.y - FOOD_SOURCE_RADIUS));
+        outline.set_fill_color(Color::TRANSPARENT);
+        outline.set_outline_thickness(1.0);
+        outline.set_outline_color(Color::rgb(0, 120, 0)); // Dark green
+        window.draw(&outline);
+        
+        // Filled part shrinks as the food is taken
+        let fraction = if self.food.initial > 0.0 {
+            (self.food.remaining / self.food.initial).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let radius = FOOD_SOURCE_RADIUS * fraction.sqrt();
+        let mut fill = CircleShape::new(radius, 16);
+        fill.set_position(Vector2f::new(position.x - radius, position.y - radius));
+        fill.set_fill_color(Color::rgb(50, 200, 50)); // Green, like food cells
+        window.draw(&fill);
+    }
+}
@@ -0,0 +1,27 @@
+// SFML front-end for the simulation.
+// The simulation core has no knowledge of windows or drawing; everything
+// that puts pixels on screen lives here and only reads simulation state.
+use sfml::graphics::RenderWindow;
+
+pub mod ant;
+pub mod colony;
+pub mod ecs;
+pub mod environment;
+pub mod food;
+pub mod pheromone;
+pub mod simulation;
+
+pub use self::ecs::RenderingSystem;
+pub use self::pheromone::{Colormap, PheromoneHeatmap};
+pub use self::simulation::SimulationRenderer;
+
+/// Anything from the simulation that can be drawn to a window
+pub trait Render {
+    fn render(&self, window: &mut RenderWindow);
+}
+
+impl From<crate::math::Vector2f> for sfml::system::Vector2f {
+    fn from(vec: crate::math::Vector2f) -> Self {
+        sfml::system::Vector2f::new(vec.x, vec.y)
+    }
+}
@@ -0,0 +1,257 @@
+use sfml::graphics::{RenderWindow, RenderTarget, Sprite, Texture, Transformable, BlendMode, RenderStates};
+use sfml::cpp::FBox;
+use log::warn;
+
+use crate::ecs::entity::EntityId;
+use crate::pheromone::{PheromoneSystem, PheromoneType};
+
+// How much of a colony's tint goes into its pheromone colours
+const COLONY_TINT: f32 = 0.4;
+
+// Colour stops of the scalar colormaps, from no pheromone to the max strength
+const HEAT_STOPS: [(u8, u8, u8); 4] = [(80, 0, 0), (255, 0, 0), (255, 255, 0), (255, 255, 255)];
+const VIRIDIS_STOPS: [(u8, u8, u8); 5] = [(68, 1, 84), (59, 82, 139), (33, 145, 140), (94, 201, 98), (253, 231, 37)];
+const GRAYSCALE_STOPS: [(u8, u8, u8); 2] = [(160, 160, 160), (0, 0, 0)];
+
+/// How the pheromone field is coloured
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    /// A colour per pheromone type, tinted per colony and added onto the ground:
+    /// green for food, magenta for home, orange-red for alarm
+    #[default]
+    Classic,
+    /// The strongest visible pheromone in each cell, from dark red through yellow to white
+    Heat,
+    /// The strongest visible pheromone in each cell, from purple through teal to yellow
+    Viridis,
+    /// The strongest visible pheromone in each cell, from light grey to black
+    Grayscale,
+}
+
+impl Colormap {
+    /// Every colormap, in the order they are cycled through
+    pub const ALL: [Colormap; 4] = [Colormap::Classic, Colormap::Heat, Colormap::Viridis, Colormap::Grayscale];
+
+    /// The colormap after this one, wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&colormap| colormap == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    // Colour stops for the scalar colormaps; Classic colours by type instead
+    fn stops(self) -> &'static [(u8, u8, u8)] {
+        match self {
+            Colormap::Classic | Colormap::Heat => &HEAT_STOPS,
+            Colormap::Viridis => &VIRIDIS_STOPS,
+            Colormap::Grayscale => &GRAYSCALE_STOPS,
+        }
+    }
+}
+
+/// Draws the pheromone field as one texture with a pixel per pheromone cell.
+/// The texture is filled on the CPU and uploaded once per frame, so drawing
+/// costs the same however many cells hold pheromone. It is smoothed when
+/// scaled up, so each cell shows as a soft blob rather than a hard square.
+pub struct PheromoneHeatmap {
+    colormap: Colormap,
+    // Whether each pheromone type is drawn, indexed by `PheromoneType::index`
+    visible: [bool; PheromoneType::COUNT],
+    // RGBA pixels of the texture, one per pheromone cell, rebuilt every frame
+    pixels: Vec<u8>,
+    // Strongest visible pheromone in each cell as a share of its max strength, for the scalar colormaps
+    intensity: Vec<f32>,
+    // Created on the first frame and again whenever the grid changes size
+    texture: Option<FBox<Texture>>,
+}
+
+impl Default for PheromoneHeatmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PheromoneHeatmap {
+    pub fn new() -> Self {
+        Self {
+            colormap: Colormap::default(),
+            visible: [true; PheromoneType::COUNT],
+            pixels: Vec::new(),
+            intensity: Vec::new(),
+            texture: None,
+        }
+    }
+
+    pub fn get_colormap(&self) -> Colormap {
+        self.colormap
+    }
+
+    pub fn set_colormap(&mut self, colormap: Colormap) {
+        self.colormap = colormap;
+    }
+
+    pub fn is_visible(&self, pheromone_type: PheromoneType) -> bool {
+        self.visible[pheromone_type.index()]
+    }
+
+    pub fn set_visible(&mut self, pheromone_type: PheromoneType, visible: bool) {
+        self.visible[pheromone_type.index()] = visible;
+    }
+
+    pub fn render(&mut self, pheromones: &PheromoneSystem, window: &mut RenderWindow) {
+        let (width, height) = pheromones.get_grid_dimensions();
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.pixels.clear();
+        self.pixels.resize(width * height * 4, 0);
+
+        if self.colormap == Colormap::Classic {
+            self.fill_classic(pheromones, width);
+        } else {
+            self.fill_scalar(pheromones, width, height);
+        }
+
+        // Classic colours add up onto the ground like light; the scalar colormaps cover it
+        let states = RenderStates {
+            blend_mode: if self.colormap == Colormap::Classic { BlendMode::ADD } else { BlendMode::ALPHA },
+            ..Default::default()
+        };
+
+        let Some(texture) = texture_for(&mut self.texture, width as u32, height as u32) else {
+            return;
+        };
+        texture.update_from_pixels(&self.pixels, width as u32, height as u32, 0, 0);
+
+        let mut sprite = Sprite::with_texture(texture);
+        let grid_size = pheromones.get_grid_size();
+        sprite.set_scale((grid_size, grid_size));
+        window.draw_with_renderstates(&sprite, &states);
+    }
+
+    // Add up every visible type's colour in each cell, weighted by strength.
+    // Pixels are drawn additively at full alpha, so this matches drawing each
+    // cell's pheromones one by one with additive blending.
+    fn fill_classic(&mut self, pheromones: &PheromoneSystem, width: usize) {
+        // Empty cells are opaque black, which adds nothing, so smoothing fades
+        // colours out evenly toward them
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+
+        for ((grid_x, grid_y, pheromone_type), colony, strength) in pheromones.get_all_pheromones() {
+            if !self.visible[pheromone_type.index()] {
+                continue;
+            }
+
+            let max_strength = pheromones.get_config(pheromone_type).max_strength;
+            let alpha = (strength / max_strength).min(1.0);
+
+            let base = match pheromone_type {
+                PheromoneType::Food => (0, 255, 100),   // Brighter green for food pheromones
+                PheromoneType::Home => (255, 50, 255),  // Brighter magenta for home pheromones
+                PheromoneType::Alarm => (255, 70, 0),   // Orange-red for alarm pheromones
+            };
+
+            // Tint colony trails so colonies can be told apart; shared scents keep the plain colour
+            let (r, g, b) = match colony {
+                Some(colony_id) => tint(base, colony_tint(colony_id)),
+                None => base,
+            };
+
+            let pixel = (grid_y * width + grid_x) * 4;
+            for (channel, value) in [r, g, b].into_iter().enumerate() {
+                let added = (value as f32 * alpha) as u8;
+                self.pixels[pixel + channel] = self.pixels[pixel + channel].saturating_add(added);
+            }
+        }
+    }
+
+    // Colour each cell by its strongest visible pheromone, fading out where it is weak
+    fn fill_scalar(&mut self, pheromones: &PheromoneSystem, width: usize, height: usize) {
+        self.intensity.clear();
+        self.intensity.resize(width * height, 0.0);
+
+        for ((grid_x, grid_y, pheromone_type), _, strength) in pheromones.get_all_pheromones() {
+            if !self.visible[pheromone_type.index()] {
+                continue;
+            }
+
+            let max_strength = pheromones.get_config(pheromone_type).max_strength;
+            let cell = grid_y * width + grid_x;
+            self.intensity[cell] = self.intensity[cell].max((strength / max_strength).min(1.0));
+        }
+
+        let stops = self.colormap.stops();
+        for (cell, &intensity) in self.intensity.iter().enumerate() {
+            if intensity <= 0.0 {
+                // Transparent, but in the faintest colour so smoothing doesn't darken the edges
+                let (r, g, b) = stops[0];
+                self.pixels[cell * 4..cell * 4 + 4].copy_from_slice(&[r, g, b, 0]);
+                continue;
+            }
+
+            let (r, g, b) = sample(stops, intensity);
+            self.pixels[cell * 4..cell * 4 + 4].copy_from_slice(&[r, g, b, (intensity * 255.0) as u8]);
+        }
+    }
+}
+
+// The texture to upload a frame into, recreated if the grid changed size
+fn texture_for(texture: &mut Option<FBox<Texture>>, width: u32, height: u32) -> Option<&mut FBox<Texture>> {
+    let fits = texture.as_ref()
+        .is_some_and(|texture| texture.size().x == width && texture.size().y == height);
+
+    if !fits {
+        let created = Texture::new().and_then(|mut texture| {
+            texture.create(width, height)?;
+            // Blend between cell centres like the soft dots pheromones used to be drawn as
+            texture.set_smooth(true);
+            Ok(texture)
+        });
+
+        *texture = match created {
+            Ok(created) => Some(created),
+            Err(e) => {
+                warn!("Failed to create the pheromone texture: {:?}", e);
+                None
+            }
+        };
+    }
+
+    texture.as_mut()
+}
+
+// Colour at `t` (0 to 1) along evenly spaced colour stops
+fn sample(stops: &[(u8, u8, u8)], t: f32) -> (u8, u8, u8) {
+    let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let index = (position as usize).min(stops.len() - 2);
+    let fraction = position - index as f32;
+
+    let (from, to) = (stops[index], stops[index + 1]);
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * fraction) as u8;
+    (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+// A colour for each colony. Hues are spread by the golden ratio so
+// colonies created one after another get clearly different colours.
+fn colony_tint(colony_id: EntityId) -> (u8, u8, u8) {
+    let hue = (colony_id.index() as f32 * 0.618_034).fract() * 6.0;
+    let rising = (hue.fract() * 255.0) as u8;
+    let falling = 255 - rising;
+
+    match hue as u32 {
+        0 => (255, rising, 0),
+        1 => (falling, 255, 0),
+        2 => (0, 255, rising),
+        3 => (0, falling, 255),
+        4 => (rising, 0, 255),
+        _ => (255, 0, falling),
+    }
+}
+
+// Blend a pheromone colour toward a colony's tint
+fn tint(base: (u8, u8, u8), tint: (u8, u8, u8)) -> (u8, u8, u8) {
+    let mix = |base: u8, tint: u8| (base as f32 * (1.0 - COLONY_TINT) + tint as f32 * COLONY_TINT) as u8;
+    (mix(base.0, tint.0), mix(base.1, tint.1), mix(base.2, tint.2))
+}
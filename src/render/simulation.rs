@@ -0,0 +1,42 @@
+use sfml::graphics::RenderWindow;
+
+use crate::simulation::Simulation;
+use super::pheromone::PheromoneHeatmap;
+use super::Render;
+
+/// Draws the whole simulation. Unlike the `Render` impls it keeps state
+/// between frames: the pheromone heatmap's texture and display settings.
+#[derive(Default)]
+pub struct SimulationRenderer {
+    heatmap: PheromoneHeatmap,
+}
+
+impl SimulationRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn heatmap(&mut self) -> &mut PheromoneHeatmap {
+        &mut self.heatmap
+    }
+    
+    pub fn render(&mut self, simulation: &Simulation, window: &mut RenderWindow) {
+        // Render the grid, then the pheromones over it
+        let environment = simulation.environment_ref();
+        environment.render(window);
+        self.heatmap.render(environment.pheromone_system_ref(), window);
+        
+        // Render food sources and colonies, then the ants on top of them
+        for food_source in simulation.get_food_sources() {
+            food_source.render(window);
+        }
+        
+        for (_, colony) in simulation.get_colonies() {
+            colony.render(window);
+        }
+        
+        for ant in simulation.get_ants() {
+            ant.render(window);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+// Single seedable random number generator shared by the whole simulation.
+// Every random decision (ant spawning, wandering, turning, ...) draws from
+// this generator, so two runs with the same seed and the same inputs
+// produce identical ant trajectories. It is the same ChaCha12 generator as
+// rand's `StdRng`, used directly so its position in the stream can be saved.
+pub struct SimRng {
+    seed: u64,
+    rng: ChaCha12Rng,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+        }
+    }
+
+    // Pick a fresh seed from the OS so the run can still be replayed later
+    pub fn from_entropy() -> Self {
+        Self::new(rand::random::<u64>())
+    }
+
+    // The seed this generator was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // How far along its stream the generator is, in 32-bit words drawn since it was seeded
+    pub fn position(&self) -> u128 {
+        self.rng.get_word_pos()
+    }
+
+    // Continue the stream from a position returned by `position`,
+    // e.g. to pick up a saved run where it left off
+    pub fn set_position(&mut self, position: u128) {
+        self.rng.set_word_pos(position);
+    }
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_stream() {
+        let mut a = SimRng::new(7);
+        let mut b = SimRng::new(7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn restoring_the_position_continues_the_stream() {
+        let mut original = SimRng::new(42);
+        for _ in 0..37 {
+            original.next_u32();
+        }
+
+        let mut restored = SimRng::new(42);
+        restored.set_position(original.position());
+        for _ in 0..100 {
+            assert_eq!(original.next_u32(), restored.next_u32());
+        }
+    }
+}
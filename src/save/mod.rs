@@ -1,15 +1,20 @@
 use serde::{Serialize, Deserialize};
-use sfml::system::Vector2f;
 use std::fs::File;
 use std::io::{self, Read, Write};
 use std::path::Path;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::environment::{Environment, CellType};
+use crate::environment::{Environment, CellType, FoodConfig};
+use crate::ant::{AntParams, Steering};
 use crate::colony::Colony;
-use crate::ant::Ant;
-use crate::pheromone::PheromoneType;
-use crate::game::Game;
+use crate::ecs::component::{
+    AntRole, AntState, AntStateComponent, FoodComponent, HealthComponent, PheromoneComponent, PositionRecord,
+    VelocityComponent,
+};
+use crate::ecs::entity::EntityId;
+use crate::math::Vector2f;
+use crate::pheromone::{PheromoneConfig, PheromoneType, Wind};
+use crate::simulation::{AntRef, FoodSourceRef, Simulation, COLONY_RADIUS};
 
 // Serializable versions of our game structs
 #[derive(Serialize, Deserialize, Clone)]
@@ -39,30 +44,110 @@ pub struct SavedAnt {
     pub direction: f32,
     pub speed: f32,
     pub carrying_food: bool,
+    // Older saves don't record the load; their ants carry one unit, what every delivery used to be worth
+    #[serde(default)]
+    pub carried_food: Option<f32>,
     pub home_position: SavedVector2f,
     pub pheromone_deposit_timer: f32,
+    // Older saves have no health; those ants start out fed
+    #[serde(default)]
+    pub health: Option<f32>,
+    // Older saves have no roles; those ants are workers
+    #[serde(default)]
+    pub role: AntRole,
+    // Older saves don't keep what the ant was up to; those ants start their trip over
+    #[serde(default)]
+    pub state: Option<AntState>,
+    #[serde(default)]
+    pub ignore_pheromones_timer: f32,
+    #[serde(default)]
+    pub lifetime: f32,
+    #[serde(default)]
+    pub last_position_record: f32,
+    #[serde(default)]
+    pub position_history: Vec<SavedPositionRecord>,
+    #[serde(default)]
+    pub trail_time: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedPositionRecord {
+    position: SavedVector2f,
+    time: f32,
 }
 
 impl SavedAnt {
-    pub fn from_ant(ant: &Ant) -> Self {
+    pub fn from_ant(ant: &AntRef) -> Self {
         Self {
-            position: SavedVector2f::from(ant.get_position()),
-            direction: ant.get_direction(),
-            speed: ant.get_speed(),
-            carrying_food: ant.is_carrying_food(),
-            home_position: SavedVector2f::from(ant.get_home_position()),
-            pheromone_deposit_timer: ant.get_pheromone_deposit_timer(),
+            position: SavedVector2f { x: ant.position.x, y: ant.position.y },
+            direction: ant.velocity.direction,
+            speed: ant.velocity.speed,
+            carrying_food: ant.state.carrying_food,
+            carried_food: Some(ant.state.carried_food),
+            home_position: SavedVector2f::from(ant.state.home_position),
+            pheromone_deposit_timer: ant.state.pheromone_timer,
+            health: ant.health.map(|health| health.current),
+            role: ant.state.role,
+            state: Some(ant.state.state.clone()),
+            ignore_pheromones_timer: ant.state.ignore_pheromones_timer,
+            lifetime: ant.state.lifetime,
+            last_position_record: ant.state.last_position_record,
+            position_history: ant.state.position_history.iter()
+                .map(|record| SavedPositionRecord { position: SavedVector2f::from(record.position), time: record.time })
+                .collect(),
+            trail_time: ant.state.trail_time,
         }
     }
     
-    pub fn to_ant(&self) -> Ant {
-        let mut ant = Ant::new(self.position.x, self.position.y);
-        ant.set_direction(self.direction);
-        ant.set_speed(self.speed);
-        ant.set_carrying_food(self.carrying_food);
-        ant.set_home_position(Vector2f::new(self.home_position.x, self.home_position.y));
-        ant.set_pheromone_deposit_timer(self.pheromone_deposit_timer);
-        ant
+    // Spawn the saved ant into the simulation as a member of `colony_id`
+    pub fn spawn(&self, simulation: &mut Simulation, colony_id: Option<EntityId>) -> EntityId {
+        let entity_id = simulation.add_ant(self.position.x, self.position.y, colony_id);
+        let world = simulation.world();
+        
+        if let Some(velocity) = world.get_component_mut::<VelocityComponent>(entity_id) {
+            velocity.direction = self.direction;
+            velocity.dx = self.direction.cos();
+            velocity.dy = self.direction.sin();
+            velocity.speed = self.speed;
+        }
+        
+        if let Some(state) = world.get_component_mut::<AntStateComponent>(entity_id) {
+            state.carrying_food = self.carrying_food;
+            state.carried_food = match self.carried_food {
+                Some(amount) => amount,
+                None if self.carrying_food => 1.0,
+                None => 0.0,
+            };
+            state.home_position = Vector2f::new(self.home_position.x, self.home_position.y);
+            state.pheromone_timer = self.pheromone_deposit_timer;
+            state.role = self.role;
+            state.state = match &self.state {
+                Some(saved_state) => saved_state.clone(),
+                None if self.carrying_food => AntState::ReturningHome,
+                None => AntState::SearchingForFood,
+            };
+            state.ignore_pheromones_timer = self.ignore_pheromones_timer;
+            state.lifetime = self.lifetime;
+            state.last_position_record = self.last_position_record;
+            state.position_history = self.position_history.iter()
+                .map(|record| PositionRecord { position: record.position.clone().into(), time: record.time })
+                .collect();
+            state.trail_time = self.trail_time;
+        }
+        
+        if let (Some(health), Some(saved_health)) = (world.get_component_mut::<HealthComponent>(entity_id), self.health) {
+            health.current = saved_health;
+        }
+        
+        entity_id
+    }
+    
+    // Save every ant that doesn't belong to a colony
+    pub fn free_ants(simulation: &Simulation) -> Vec<SavedAnt> {
+        simulation.get_ants().iter()
+            .filter(|ant| ant.state.colony_id.is_none())
+            .map(SavedAnt::from_ant)
+            .collect()
     }
 }
 
@@ -73,12 +158,24 @@ pub struct SavedColony {
     food_stored: f32,
     max_ants: usize,
     food_deliveries: u32,
+    #[serde(default)]
+    steering: Steering,
+    // Older saves have no ant tuning; their colonies get the defaults
+    #[serde(default)]
+    params: AntParams,
+    // Older saves have no radius; their colonies get the standard one
+    #[serde(default)]
+    radius: Option<f32>,
+    // Ants the colony has raised but not spawned yet
+    #[serde(default)]
+    pending_spawns: usize,
 }
 
 impl SavedColony {
-    pub fn from_colony(colony: &Colony) -> Self {
-        let ants: Vec<SavedAnt> = colony.get_ants().iter()
-            .map(|ant| SavedAnt::from_ant(ant))
+    pub fn from_colony(colony_id: EntityId, colony: &Colony, simulation: &Simulation) -> Self {
+        let ants: Vec<SavedAnt> = simulation.get_ants().iter()
+            .filter(|ant| ant.state.colony_id == Some(colony_id))
+            .map(SavedAnt::from_ant)
             .collect();
             
         Self {
@@ -87,24 +184,64 @@ impl SavedColony {
             food_stored: colony.get_food_stored(),
             max_ants: colony.get_max_ants(),
             food_deliveries: colony.get_food_deliveries(),
+            steering: colony.get_steering(),
+            params: colony.get_params().clone(),
+            radius: Some(colony.get_radius()),
+            pending_spawns: colony.get_pending_spawns(),
         }
     }
     
-    pub fn to_colony(&self) -> Colony {
-        let mut colony = Colony::new(Vector2f::new(self.position.x, self.position.y), 30.0);
-        
-        // Remove default ants and replace with saved ants
-        colony.clear_ants();
-        
-        for saved_ant in &self.ants {
-            colony.add_ant(saved_ant.to_ant());
-        }
+    pub fn spawn(&self, simulation: &mut Simulation) -> EntityId {
+        let radius = self.radius.unwrap_or(COLONY_RADIUS);
+        let mut colony = Colony::new(Vector2f::new(self.position.x, self.position.y), radius);
         
+        // The saved ants replace the colony's starting ants
+        colony.set_pending_spawns(self.pending_spawns);
+        colony.set_ant_count(self.ants.len());
         colony.set_food_stored(self.food_stored);
         colony.set_max_ants(self.max_ants);
         colony.set_food_deliveries(self.food_deliveries);
+        colony.set_steering(self.steering);
+        colony.set_params(self.params.clone());
         
-        colony
+        let colony_id = simulation.add_colony(colony);
+        for saved_ant in &self.ants {
+            saved_ant.spawn(simulation, Some(colony_id));
+        }
+        
+        colony_id
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedFoodSource {
+    position: SavedVector2f,
+    remaining: f32,
+    initial: f32,
+    // Time left until the source next gives off scent. Older saves give it off right away
+    #[serde(default)]
+    scent_timer: f32,
+}
+
+impl SavedFoodSource {
+    pub fn from_food_source(source: &FoodSourceRef) -> Self {
+        Self {
+            position: SavedVector2f { x: source.position.x, y: source.position.y },
+            remaining: source.food.remaining,
+            initial: source.food.initial,
+            scent_timer: source.scent.map_or(0.0, |scent| scent.timer),
+        }
+    }
+    
+    pub fn spawn(&self, simulation: &mut Simulation) -> EntityId {
+        let entity_id = simulation.add_food_source(self.position.x, self.position.y, self.initial);
+        if let Some(food) = simulation.world().get_component_mut::<FoodComponent>(entity_id) {
+            food.remaining = self.remaining;
+        }
+        if let Some(scent) = simulation.world().get_component_mut::<PheromoneComponent>(entity_id) {
+            scent.timer = self.scent_timer;
+        }
+        entity_id
     }
 }
 
@@ -113,6 +250,10 @@ pub struct SavedPheromone {
     grid_x: usize,
     grid_y: usize,
     pheromone_type: PheromoneType,
+    // Index of the colony that laid it in `SavedEnvironment::colonies`, None for the shared channel.
+    // Older saves have no colonies here; their pheromones go in the shared channel.
+    #[serde(default)]
+    colony: Option<usize>,
     strength: f32,
 }
 
@@ -124,25 +265,66 @@ pub struct SavedEnvironment {
     grid_height: usize,
     grid: Vec<CellType>,
     food_amounts: Vec<((usize, usize), f32)>,
+    // Older saves don't keep the food cells that ran out; those stay empty ground
+    #[serde(default)]
+    depleted_food: Vec<((usize, usize), f32)>,
     pheromones: Vec<SavedPheromone>,
     colonies: Vec<SavedColony>,
+    #[serde(default)]
+    food_sources: Vec<SavedFoodSource>,
+    // Older saves have no seed; they get a fresh one when loaded
+    #[serde(default)]
+    seed: Option<u64>,
+    // How far the random stream had got, so a loaded run carries on where it was saved.
+    // Older saves start the stream from the beginning of their seed
+    #[serde(default)]
+    rng_position: Option<u128>,
+    #[serde(default)]
+    pheromone_diffusion: f32,
+    // Older saves have no pheromone settings; they get the defaults
+    #[serde(default)]
+    pheromone_configs: BTreeMap<PheromoneType, PheromoneConfig>,
+    #[serde(default)]
+    cross_colony_pheromones: bool,
+    #[serde(default)]
+    wind: Wind,
+    #[serde(default)]
+    food_config: FoodConfig,
+    #[serde(default)]
+    food_clock: f64,
 }
 
 impl SavedEnvironment {
-    pub fn from_environment(env: &Environment) -> Self {
-        let pheromones = env.pheromone_system_ref().get_all_pheromones().iter()
-            .map(|((grid_x, grid_y, ptype), strength)| {
-                SavedPheromone {
-                    grid_x: *grid_x,
-                    grid_y: *grid_y,
-                    pheromone_type: *ptype,
-                    strength: *strength,
-                }
+    pub fn from_simulation(simulation: &Simulation) -> Self {
+        let env = simulation.environment_ref();
+        let colony_ids: Vec<EntityId> = simulation.get_colonies().into_iter()
+            .map(|(colony_id, _)| colony_id)
+            .collect();
+        
+        // Entity ids change when a save is loaded, so pheromones refer to colonies by position
+        let pheromones = env.pheromone_system_ref().get_all_pheromones()
+            .filter_map(|((grid_x, grid_y, pheromone_type), colony_id, strength)| {
+                let colony = match colony_id {
+                    Some(colony_id) => Some(colony_ids.iter().position(|&id| id == colony_id)?),
+                    None => None,
+                };
+                
+                Some(SavedPheromone {
+                    grid_x,
+                    grid_y,
+                    pheromone_type,
+                    colony,
+                    strength,
+                })
             })
             .collect();
             
-        let colonies = env.get_all_colonies().iter()
-            .map(|colony| SavedColony::from_colony(colony))
+        let colonies = simulation.get_colonies().into_iter()
+            .map(|(colony_id, colony)| SavedColony::from_colony(colony_id, colony, simulation))
+            .collect();
+            
+        let food_sources = simulation.get_food_sources().iter()
+            .map(SavedFoodSource::from_food_source)
             .collect();
             
         Self {
@@ -152,13 +334,35 @@ impl SavedEnvironment {
             grid_height: env.get_grid_height(),
             grid: env.get_grid().clone(),
             food_amounts: env.get_food_amounts().clone().into_iter().collect(),
+            depleted_food: env.get_depleted_food().clone().into_iter().collect(),
             pheromones,
             colonies,
+            food_sources,
+            seed: Some(env.get_seed()),
+            rng_position: Some(env.get_rng_position()),
+            pheromone_diffusion: env.pheromone_system_ref().get_diffusion_rate(),
+            pheromone_configs: PheromoneType::ALL.into_iter()
+                .map(|pheromone_type| (pheromone_type, *env.pheromone_system_ref().get_config(pheromone_type)))
+                .collect(),
+            cross_colony_pheromones: env.pheromone_system_ref().get_cross_colony_sensing(),
+            wind: env.pheromone_system_ref().get_wind().clone(),
+            food_config: env.get_food_config().clone(),
+            food_clock: env.get_food_clock(),
         }
     }
     
-    pub fn to_environment(&self) -> Environment {
-        let mut env = Environment::new(self.width, self.height);
+    // Replace the stored seed, e.g. to replay a save with a different seed.
+    // The random stream then starts from the beginning of the new seed.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+        self.rng_position = None;
+    }
+    
+    pub fn to_simulation(&self) -> Simulation {
+        let mut env = match self.seed {
+            Some(seed) => Environment::with_seed(self.width, self.height, seed),
+            None => Environment::new(self.width, self.height),
+        };
         
         // Set the grid
         env.set_grid(self.grid.clone(), self.grid_width, self.grid_height);
@@ -166,24 +370,56 @@ impl SavedEnvironment {
         // Set food amounts
         let food_amounts: HashMap<(usize, usize), f32> = self.food_amounts.clone().into_iter().collect();
         env.set_food_amounts(food_amounts);
+        env.set_depleted_food(self.depleted_food.iter().copied().collect());
+        env.set_food_config(self.food_config.clone());
+        env.set_food_clock(self.food_clock);
+        
+        env.pheromone_system().set_diffusion_rate(self.pheromone_diffusion);
+        env.pheromone_system().set_cross_colony_sensing(self.cross_colony_pheromones);
+        env.pheromone_system().set_wind(self.wind.clone());
+        for (pheromone_type, config) in &self.pheromone_configs {
+            env.pheromone_system().set_config(*pheromone_type, *config);
+        }
+        
+        // Add colonies and their ants
+        let mut simulation = Simulation::new(env);
+        let colony_ids: Vec<EntityId> = self.colonies.iter()
+            .map(|colony| colony.spawn(&mut simulation))
+            .collect();
         
-        // Add pheromones
+        // Add pheromones, now that the colonies that laid them exist
         for pheromone in &self.pheromones {
-            env.pheromone_system().add_pheromone_at_grid(
+            let colony_id = match pheromone.colony {
+                Some(index) => match colony_ids.get(index) {
+                    Some(&colony_id) => Some(colony_id),
+                    None => continue,
+                },
+                None => None,
+            };
+            
+            simulation.environment().pheromone_system().add_pheromone_at_grid(
                 pheromone.grid_x,
                 pheromone.grid_y,
                 pheromone.pheromone_type,
+                colony_id,
                 pheromone.strength
             );
         }
         
-        // Add colonies
-        env.clear_colonies();
-        for colony in &self.colonies {
-            env.add_colony(colony.to_colony());
+        for food_source in &self.food_sources {
+            food_source.spawn(&mut simulation);
         }
         
-        env
+        self.restore_rng_position(&mut simulation);
+        simulation
+    }
+    
+    // Pick the random stream up where it was saved. Spawning ants draws from
+    // it, so this has to come after the world has been rebuilt.
+    fn restore_rng_position(&self, simulation: &mut Simulation) {
+        if let Some(position) = self.rng_position {
+            simulation.environment().set_rng_position(position);
+        }
     }
 }
 
@@ -193,26 +429,25 @@ pub struct SavedGame {
     pub interaction_mode: String,
     pub simulation_speed: f32,
     pub paused: bool,
-    pub test_ants: Vec<SavedAnt>,
+    pub test_ants: Vec<SavedAnt>, // Ants that don't belong to a colony
+    #[serde(default)]
+    pub tick: u64,
 }
 
 impl SavedGame {
-    pub fn from_game(game: &Game) -> Self {
-        Self {
-            environment: SavedEnvironment::from_environment(game.get_environment()),
-            interaction_mode: format!("{:?}", game.get_interaction_mode()),
-            simulation_speed: game.get_simulation_speed(),
-            paused: game.is_paused(),
-            test_ants: game.get_test_ants().iter()
-                .map(|ant| SavedAnt::from_ant(ant))
-                .collect(),
+    // Restore the simulation, including the ants that don't belong to a colony
+    pub fn to_simulation(&self) -> Simulation {
+        let mut simulation = self.environment.to_simulation();
+        for saved_ant in &self.test_ants {
+            saved_ant.spawn(&mut simulation, None);
         }
+        self.environment.restore_rng_position(&mut simulation);
+        simulation
     }
 }
 
-pub fn save_game_state(path: &Path, game: &Game) -> io::Result<()> {
-    let game_state = SavedGame::from_game(game);
-    let serialized = serde_json::to_string_pretty(&game_state)?;
+pub fn save_game_state(path: &Path, game_state: &SavedGame) -> io::Result<()> {
+    let serialized = serde_json::to_string_pretty(game_state)?;
     let mut file = File::create(path)?;
     file.write_all(serialized.as_bytes())?;
     Ok(())
@@ -224,5 +459,93 @@ pub fn load_game_state(path: &Path) -> io::Result<SavedGame> {
     file.read_to_string(&mut contents)?;
     
     let game_state: SavedGame = serde_json::from_str(&contents)?;
+    game_state.environment.food_config.validate().map_err(|message| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("food_config: {}", message))
+    })?;
+    for (index, colony) in game_state.environment.colonies.iter().enumerate() {
+        colony.params.validate().map_err(|message| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("colony {}: {}", index, message))
+        })?;
+    }
     Ok(game_state)
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    #[test]
+    fn loaded_save_continues_the_random_stream() {
+        let mut simulation = Simulation::new(Environment::with_seed(200, 200, 3));
+        simulation.add_ant_nest(100.0, 100.0);
+        for _ in 0..30 {
+            simulation.update(1.0 / 60.0);
+        }
+
+        let saved = SavedEnvironment::from_simulation(&simulation);
+        let json = serde_json::to_string(&saved).unwrap();
+        let loaded: SavedEnvironment = serde_json::from_str(&json).unwrap();
+        let mut restored = loaded.to_simulation();
+
+        let position = simulation.environment_ref().get_rng_position();
+        assert_eq!(restored.environment_ref().get_rng_position(), position);
+        assert_eq!(restored.environment().rng().next_u64(), simulation.environment().rng().next_u64());
+    }
+
+    #[test]
+    fn new_seed_restarts_the_random_stream() {
+        let mut simulation = Simulation::new(Environment::with_seed(200, 200, 3));
+        simulation.environment().rng().next_u64();
+
+        let mut saved = SavedEnvironment::from_simulation(&simulation);
+        saved.set_seed(9);
+        let restored = saved.to_simulation();
+
+        assert_eq!(restored.environment_ref().get_seed(), 9);
+        assert_eq!(restored.environment_ref().get_rng_position(), 0);
+    }
+
+    // Where every ant is, where it is heading and what it is doing, with what
+    // each colony has stored and all the pheromone on the map
+    fn snapshot(simulation: &Simulation) -> (Vec<String>, Vec<f32>, f32) {
+        let ants = simulation.get_ants().iter()
+            .map(|ant| format!(
+                "{} {} {} {:?} {} {}",
+                ant.position.x, ant.position.y, ant.velocity.direction,
+                ant.state.state, ant.state.carried_food, ant.state.ignore_pheromones_timer
+            ))
+            .collect();
+        let stored = simulation.get_colonies().iter().map(|(_, colony)| colony.get_food_stored()).collect();
+        let pheromone = simulation.environment_ref().pheromone_system_ref().get_all_pheromones()
+            .map(|(_, _, strength)| strength)
+            .sum();
+        (ants, stored, pheromone)
+    }
+
+    #[test]
+    fn loaded_run_carries_on_like_the_original() {
+        let mut simulation = Simulation::new(Environment::with_seed(300, 300, 11));
+        simulation.add_ant_nest(100.0, 150.0);
+        for y in [140.0, 150.0, 160.0] {
+            simulation.environment().add_food(140.0, y);
+        }
+        simulation.add_food_source(200.0, 150.0, 50.0);
+        for _ in 0..600 {
+            simulation.update(1.0 / 60.0);
+        }
+
+        let saved = SavedEnvironment::from_simulation(&simulation);
+        let json = serde_json::to_string(&saved).unwrap();
+        let loaded: SavedEnvironment = serde_json::from_str(&json).unwrap();
+        let mut restored = loaded.to_simulation();
+        assert_eq!(snapshot(&restored), snapshot(&simulation));
+
+        for _ in 0..600 {
+            simulation.update(1.0 / 60.0);
+            restored.update(1.0 / 60.0);
+        }
+        assert_eq!(snapshot(&restored), snapshot(&simulation));
+        assert!(simulation.get_colonies()[0].1.get_food_deliveries() > 0, "the run should get as far as delivering food");
+    }
+}
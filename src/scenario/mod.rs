@@ -0,0 +1,132 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::ant::{AntParams, Steering};
+use crate::environment::{Environment, FoodConfig};
+use crate::pheromone::{PheromoneConfig, PheromoneType, Wind};
+use crate::simulation::Simulation;
+
+// A point in screen coordinates used by scenario files
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct ScenarioPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+// A nest and the colony living in it
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScenarioNest {
+    pub x: f32,
+    pub y: f32,
+    // How the colony's ants follow trails
+    #[serde(default)]
+    pub steering: Steering,
+    // Tuning for the colony's ants; fields left out keep their defaults
+    #[serde(default)]
+    pub ant_params: AntParams,
+}
+
+// A food source entity with a limited amount of food
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct ScenarioFoodSource {
+    pub x: f32,
+    pub y: f32,
+    pub amount: f32,
+}
+
+// A hand-written starting layout for the simulation.
+// Unlike a save file this only describes what to place in the world,
+// so it is easy to write by hand for experiments.
+#[derive(Serialize, Deserialize)]
+pub struct Scenario {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub walls: Vec<ScenarioPoint>,
+    #[serde(default)]
+    pub food: Vec<ScenarioPoint>,
+    #[serde(default)]
+    pub nests: Vec<ScenarioNest>,
+    #[serde(default)]
+    pub food_sources: Vec<ScenarioFoodSource>,
+    // Seed for the simulation's random number generator; random when absent
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // How fast pheromone spreads into neighbouring cells; 0 (the default) turns diffusion off
+    #[serde(default)]
+    pub pheromone_diffusion: f32,
+    // Decay and limits for pheromone types; types left out keep the defaults
+    #[serde(default)]
+    pub pheromones: BTreeMap<PheromoneType, PheromoneConfig>,
+    // Let ants follow other colonies' trails as well as their own
+    #[serde(default)]
+    pub cross_colony_pheromones: bool,
+    // Wind that blows pheromone downwind; calm when absent
+    #[serde(default)]
+    pub wind: Wind,
+    // How much food the `food` cells hold, how much ants take per pickup and how food grows back and spreads
+    #[serde(default)]
+    pub food_cells: FoodConfig,
+}
+
+impl Scenario {
+    // Build a fresh simulation with everything the scenario describes
+    pub fn to_simulation(&self) -> Simulation {
+        let mut env = match self.seed {
+            Some(seed) => Environment::with_seed(self.width, self.height, seed),
+            None => Environment::new(self.width, self.height),
+        };
+
+        for wall in &self.walls {
+            env.add_wall(wall.x, wall.y);
+        }
+
+        env.set_food_config(self.food_cells.clone());
+        for food in &self.food {
+            env.add_food(food.x, food.y);
+        }
+
+        env.pheromone_system().set_diffusion_rate(self.pheromone_diffusion);
+        env.pheromone_system().set_cross_colony_sensing(self.cross_colony_pheromones);
+        env.pheromone_system().set_wind(self.wind.clone());
+        for (pheromone_type, config) in &self.pheromones {
+            env.pheromone_system().set_config(*pheromone_type, *config);
+        }
+
+        let mut simulation = Simulation::new(env);
+        for nest in &self.nests {
+            if let Some(colony_id) = simulation.add_ant_nest(nest.x, nest.y) {
+                if let Some(colony) = simulation.get_colony_mut(colony_id) {
+                    colony.set_steering(nest.steering);
+                    colony.set_params(nest.ant_params.clone());
+                }
+            }
+        }
+
+        for source in &self.food_sources {
+            simulation.add_food_source(source.x, source.y, source.amount);
+        }
+
+        simulation
+    }
+}
+
+pub fn load_scenario(path: &Path) -> io::Result<Scenario> {
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+
+    let scenario: Scenario = serde_json::from_str(&contents)?;
+    scenario.food_cells.validate().map_err(|message| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("food_cells: {}", message))
+    })?;
+    for (index, nest) in scenario.nests.iter().enumerate() {
+        nest.ant_params.validate().map_err(|message| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("nest {}: {}", index, message))
+        })?;
+    }
+    Ok(scenario)
+}
@@ -0,0 +1,175 @@
+use crate::ant;
+use crate::colony::Colony;
+use crate::ecs::component::{
+    AntStateComponent, ColliderComponent, CollisionLayer, FoodComponent, HealthComponent, PheromoneComponent,
+    PositionComponent, VelocityComponent,
+};
+use crate::ecs::entity::EntityId;
+use crate::ecs::event::Subscription;
+use crate::ecs::system::{
+    AlarmSystem, AntBehaviorSystem, CollisionSystem, ColonySystem, EnvironmentSystem, EventLogSystem, FoodSystem, HealthSystem,
+    MovementSystem, PheromoneEmitterSystem,
+};
+use crate::ecs::World;
+use crate::environment::Environment;
+use crate::events::SimEvent;
+use crate::pheromone::PheromoneType;
+
+// Radius colonies are created with; ants deliver food once they touch it
+pub const COLONY_RADIUS: f32 = 30.0;
+
+// Radius of a food source entity's collider
+pub const FOOD_SOURCE_RADIUS: f32 = 8.0;
+
+// Food sources keep a food pheromone going around them so ants can find them
+const FOOD_SOURCE_SCENT_STRENGTH: f32 = 0.5;
+const FOOD_SOURCE_SCENT_INTERVAL: f32 = 1.0;
+
+// Read-only view of one ant entity's components
+pub struct AntRef<'a> {
+    pub id: EntityId,
+    pub position: &'a PositionComponent,
+    pub velocity: &'a VelocityComponent,
+    pub state: &'a AntStateComponent,
+    pub health: Option<&'a HealthComponent>,
+}
+
+// Read-only view of one food source entity's components
+pub struct FoodSourceRef<'a> {
+    pub id: EntityId,
+    pub position: &'a PositionComponent,
+    pub food: &'a FoodComponent,
+    pub scent: Option<&'a PheromoneComponent>,
+}
+
+// The whole simulation state: an ECS world holding ants and colonies as
+// entities, with the environment (grid, pheromones, rng) as a resource.
+// Front-ends and the headless runner drive it through `update`.
+pub struct Simulation {
+    world: World,
+}
+
+impl Simulation {
+    pub fn new(environment: Environment) -> Self {
+        let mut world = World::new();
+        world.add_resource(environment);
+        
+        // Colonies spawn ants, ants take food and decide where to go (behavior stage),
+        // then they move and collide (physics), pheromones evaporate and food sources
+        // give off scent (pheromone), and finally ants get hungry and the tick's
+        // events are logged (cleanup)
+        world.add_system(ColonySystem::new());
+        world.add_system(FoodSystem::new());
+        world.add_system(AntBehaviorSystem::new());
+        world.add_system(MovementSystem::new());
+        world.add_system(CollisionSystem::new());
+        world.add_system(EnvironmentSystem::new());
+        world.add_system(PheromoneEmitterSystem::new());
+        world.add_system(AlarmSystem::new());
+        world.add_system(HealthSystem::new());
+        let log_subscription = world.subscribe::<SimEvent>();
+        world.add_system(EventLogSystem::new(log_subscription));
+        
+        Self { world }
+    }
+    
+    // Advance the simulation by one tick
+    pub fn update(&mut self, delta_time: f32) {
+        self.world.update(delta_time);
+    }
+    
+    // Get the environment with mutable reference
+    pub fn environment(&mut self) -> &mut Environment {
+        self.world.get_resource_mut::<Environment>()
+            .expect("simulation world has no environment")
+    }
+    
+    // Get the environment with immutable reference
+    pub fn environment_ref(&self) -> &Environment {
+        self.world.get_resource::<Environment>()
+            .expect("simulation world has no environment")
+    }
+    
+    // Get the ECS world with mutable reference
+    pub fn world(&mut self) -> &mut World {
+        &mut self.world
+    }
+    
+    // Get the ECS world with immutable reference
+    pub fn world_ref(&self) -> &World {
+        &self.world
+    }
+    
+    // Start receiving simulation events; read them with `read_events`
+    pub fn subscribe(&mut self) -> Subscription<SimEvent> {
+        self.world.subscribe::<SimEvent>()
+    }
+    
+    // The events sent since the subscriber last read
+    pub fn read_events(&mut self, subscription: &Subscription<SimEvent>) -> impl Iterator<Item = &SimEvent> {
+        self.world.read_events(subscription)
+    }
+    
+    // Build a nest in the environment and found a colony in it
+    pub fn add_ant_nest(&mut self, x: f32, y: f32) -> Option<EntityId> {
+        let position = self.environment().add_ant_nest(x, y)?;
+        Some(self.add_colony(Colony::new(position, COLONY_RADIUS)))
+    }
+    
+    pub fn add_colony(&mut self, colony: Colony) -> EntityId {
+        let entity_id = self.world.create_entity();
+        let position = colony.get_position();
+        self.world.add_component(entity_id, PositionComponent { x: position.x, y: position.y });
+        self.world.add_component(entity_id, ColliderComponent { radius: colony.get_radius(), layer: CollisionLayer::Colony });
+        self.world.add_component(entity_id, colony);
+        entity_id
+    }
+    
+    // Place a food source entity holding `amount` food
+    pub fn add_food_source(&mut self, x: f32, y: f32, amount: f32) -> EntityId {
+        let entity_id = self.world.create_entity();
+        self.world.add_component(entity_id, PositionComponent { x, y });
+        self.world.add_component(entity_id, ColliderComponent { radius: FOOD_SOURCE_RADIUS, layer: CollisionLayer::Food });
+        self.world.add_component(entity_id, FoodComponent::new(amount));
+        self.world.add_component(entity_id, PheromoneComponent::new(
+            PheromoneType::Food,
+            FOOD_SOURCE_SCENT_STRENGTH,
+            FOOD_SOURCE_SCENT_INTERVAL,
+        ));
+        entity_id
+    }
+    
+    // Spawn an ant, optionally belonging to a colony
+    pub fn add_ant(&mut self, x: f32, y: f32, colony_id: Option<EntityId>) -> EntityId {
+        ant::spawn_ant(&mut self.world, x, y, colony_id)
+    }
+    
+    pub fn get_colony_mut(&mut self, colony_id: EntityId) -> Option<&mut Colony> {
+        self.world.get_component_mut::<Colony>(colony_id)
+    }
+    
+    // All colonies, in creation order
+    pub fn get_colonies(&self) -> Vec<(EntityId, &Colony)> {
+        self.world.query_ref::<(EntityId, &Colony)>().collect()
+    }
+    
+    // All food sources that still have food, in creation order
+    pub fn get_food_sources(&self) -> Vec<FoodSourceRef<'_>> {
+        self.world.query_ref::<(EntityId, &PositionComponent, &FoodComponent, Option<&PheromoneComponent>)>()
+            .map(|(id, position, food, scent)| FoodSourceRef { id, position, food, scent })
+            .collect()
+    }
+    
+    // All ants, in creation order
+    pub fn get_ants(&self) -> Vec<AntRef<'_>> {
+        self.world.query_ref::<(
+            EntityId,
+            &PositionComponent,
+            &VelocityComponent,
+            &AntStateComponent,
+            Option<&HealthComponent>,
+        )>()
+            .map(|(id, position, velocity, state, health)| AntRef { id, position, velocity, state, health })
+            .collect()
+    }
+}
@@ -0,0 +1,123 @@
+// Length of one simulation tick in seconds
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+
+// Most ticks we will run to catch up in a single frame. Any time beyond
+// this is dropped so a slow frame can't snowball into ever slower frames.
+pub const MAX_STEPS_PER_FRAME: u32 = 20;
+
+// Fixed-timestep accumulator that turns variable frame times into a whole
+// number of equally sized simulation ticks.
+pub struct FixedTimestep {
+    step: f32,
+    max_steps_per_frame: u32,
+    accumulator: f32,
+    tick: u64,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32, max_steps_per_frame: u32) -> Self {
+        Self {
+            step,
+            max_steps_per_frame,
+            accumulator: 0.0,
+            tick: 0,
+        }
+    }
+
+    // Add the simulated time that passed this frame and return how many
+    // ticks should run now. The tick counter advances by the same amount.
+    pub fn advance(&mut self, elapsed: f32) -> u32 {
+        self.accumulator += elapsed.max(0.0);
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps_per_frame {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        // We hit the catch-up cap, so throw away the backlog
+        if steps == self.max_steps_per_frame {
+            self.accumulator = self.accumulator.min(self.step);
+        }
+
+        self.tick += steps as u64;
+        steps
+    }
+
+    // Length of one tick in seconds
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    // Number of ticks simulated so far
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn set_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    // Forget any partially accumulated time, e.g. when pausing
+    pub fn reset_accumulator(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(FIXED_TIMESTEP, MAX_STEPS_PER_FRAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_frames_add_up_to_a_tick() {
+        let mut timestep = FixedTimestep::new(0.25, 5);
+        assert_eq!(timestep.advance(0.1), 0);
+        assert_eq!(timestep.advance(0.1), 0);
+        assert_eq!(timestep.advance(0.1), 1);
+        // The 0.05 left over carries into the next frame
+        assert_eq!(timestep.advance(0.2), 1);
+        assert_eq!(timestep.tick(), 2);
+    }
+
+    #[test]
+    fn a_long_frame_runs_several_ticks() {
+        let mut timestep = FixedTimestep::new(0.25, 5);
+        assert_eq!(timestep.advance(1.0), 4);
+        assert_eq!(timestep.advance(0.0), 0);
+        assert_eq!(timestep.tick(), 4);
+    }
+
+    #[test]
+    fn time_beyond_the_catch_up_cap_is_dropped() {
+        let mut timestep = FixedTimestep::new(0.25, 3);
+        assert_eq!(timestep.advance(2.0), 3);
+        // At most one tick's worth is kept back
+        assert_eq!(timestep.advance(0.0), 1);
+        assert_eq!(timestep.advance(0.0), 0);
+        assert_eq!(timestep.tick(), 4);
+    }
+
+    #[test]
+    fn negative_frames_and_resets_add_nothing() {
+        let mut timestep = FixedTimestep::new(0.25, 5);
+        assert_eq!(timestep.advance(-1.0), 0);
+        timestep.advance(0.2);
+        timestep.reset_accumulator();
+        assert_eq!(timestep.advance(0.1), 0);
+        assert_eq!(timestep.tick(), 0);
+    }
+
+    #[test]
+    fn the_tick_counter_can_be_restored() {
+        let mut timestep = FixedTimestep::default();
+        timestep.set_tick(100);
+        assert_eq!(timestep.advance(FIXED_TIMESTEP * 2.5), 2);
+        assert_eq!(timestep.tick(), 102);
+    }
+}
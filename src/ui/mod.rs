@@ -3,8 +3,8 @@ use sfml::system::Vector2f;
 use sfml::window::{Event, mouse};
 use sfml::cpp::FBox;
 
+use crate::events::SimEvent;
 use crate::game::InteractionMode;
-use crate::environment::Environment;
 
 // UI layout constants
 const STATUS_BAR_HEIGHT: f32 = 30.0;
@@ -15,6 +15,7 @@ pub struct UI {
     status_text: Text<'static>,
     control_text: Text<'static>,
     food_delivery_text: Text<'static>,
+    food_deliveries: u32, // Counted from FoodDelivered events
     font: Box<FBox<Font>>,
     current_mode: InteractionMode,
 }
@@ -61,6 +62,7 @@ impl UI {
             status_text,
             control_text,
             food_delivery_text,
+            food_deliveries: 0,
             font,
             current_mode: InteractionMode::None,
         }
@@ -70,32 +72,34 @@ impl UI {
         // No UI elements to interact with now
     }
     
-    pub fn update(&mut self, interaction_mode: &InteractionMode, simulation_speed: f32, paused: bool, environment: &Environment) {
+    // Keep the counters up to date with what happens in the simulation
+    pub fn handle_sim_event(&mut self, event: &SimEvent) {
+        if let SimEvent::FoodDelivered { .. } = event {
+            self.food_deliveries += 1;
+        }
+    }
+    
+    // Start counting from a known total, e.g. after loading a save
+    pub fn set_food_deliveries(&mut self, food_deliveries: u32) {
+        self.food_deliveries = food_deliveries;
+    }
+    
+    pub fn update(&mut self, interaction_mode: &InteractionMode, simulation_speed: f32, paused: bool, tick: u64) {
         // Save current mode for rendering
         self.current_mode = interaction_mode.clone();
         
         // Update status text - ONLY show game status, not controls
         let status = format!(
-            "Mode: {:?} | Speed: {:.1}x | {}",
+            "Mode: {:?} | Speed: {:.1}x | Tick: {} | {}",
             interaction_mode,
             simulation_speed,
+            tick,
             if paused { "PAUSED" } else { "Running" }
         );
         self.status_text.set_string(&status);
         
         // Update food delivery counter
-        let total_deliveries: u32 = environment.get_all_colonies().iter()
-            .map(|colony| colony.get_food_deliveries())
-            .sum();
-        
-        println!("DEBUG: UI updating food counter - Total deliveries: {}", total_deliveries);
-        
-        // Log each colony's individual count
-        for (i, colony) in environment.get_all_colonies().iter().enumerate() {
-            println!("DEBUG: Colony #{} has {} food deliveries", i, colony.get_food_deliveries());
-        }
-        
-        self.food_delivery_text.set_string(&format!("Food Deliveries: {}", total_deliveries));
+        self.food_delivery_text.set_string(&format!("Food Deliveries: {}", self.food_deliveries));
     }
     
     pub fn resize(&mut self, width: u32, height: u32) {